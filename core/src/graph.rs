@@ -0,0 +1,101 @@
+/// Links between articles identified by ids `0..len()`. Ids without an article
+/// have no links.
+#[derive(Debug, Clone, Default)]
+pub struct LinkGraph {
+    /// The ids each article links to.
+    pub articles: Vec<Vec<usize>>,
+    /// The reverse of `articles`: the ids linking to each article.
+    pub backlinks: Vec<Vec<usize>>,
+}
+
+impl LinkGraph {
+    /// A graph of the given links, indexed by id. Ids linked to past the end
+    /// are added without links.
+    pub fn new(mut articles: Vec<Vec<usize>>) -> LinkGraph {
+        let size = articles.iter().flatten().map(|&l| l + 1).max().unwrap_or(0).max(articles.len());
+        articles.resize(size, vec![]);
+
+        let mut backlinks = vec![vec![]; size];
+        for (id, links) in articles.iter().enumerate() {
+            for &link in links {
+                backlinks[link].push(id);
+            }
+        }
+
+        LinkGraph { articles, backlinks }
+    }
+
+    pub fn len(&self) -> usize {
+        self.articles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.articles.is_empty()
+    }
+
+    /// Whether the article at `from` links to the one at `to`.
+    pub fn has_edge(&self, from: usize, to: usize) -> bool {
+        self.articles.get(from).is_some_and(|links| links.contains(&to))
+    }
+
+    /// Number of links to and from an article.
+    pub fn degree(&self, id: usize) -> usize {
+        self.articles[id].len() + self.backlinks[id].len()
+    }
+
+    /// Replace the links of `id`, keeping `backlinks` in step. The graph grows
+    /// when `id` or one of its links is past the end.
+    pub fn set_links(&mut self, id: usize, links: Vec<usize>) {
+        let size = links.iter().copied().chain([id]).max().unwrap_or(0) + 1;
+        if size > self.articles.len() {
+            self.articles.resize(size, vec![]);
+            self.backlinks.resize(size, vec![]);
+        }
+
+        for &link in &self.articles[id] {
+            self.backlinks[link].retain(|&b| b != id);
+        }
+
+        for &link in &links {
+            self.backlinks[link].push(id);
+        }
+
+        self.articles[id] = links;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backlinks_reverse_links() {
+        let graph = LinkGraph::new(vec![vec![1, 2], vec![2], vec![]]);
+
+        assert_eq!(graph.backlinks, vec![vec![], vec![0], vec![0, 1]]);
+        assert_eq!(graph.degree(2), 2);
+        assert!(graph.has_edge(0, 2));
+        assert!(!graph.has_edge(2, 0));
+    }
+
+    #[test]
+    fn links_past_the_end_grow_the_graph() {
+        let graph = LinkGraph::new(vec![vec![3]]);
+
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.backlinks[3], vec![0]);
+    }
+
+    #[test]
+    fn set_links_updates_backlinks() {
+        let mut graph = LinkGraph::new(vec![vec![1], vec![]]);
+
+        graph.set_links(0, vec![4]);
+        assert_eq!(graph.len(), 5);
+        assert!(graph.backlinks[1].is_empty());
+        assert_eq!(graph.backlinks[4], vec![0]);
+
+        graph.set_links(0, vec![]);
+        assert!(graph.backlinks[4].is_empty());
+    }
+}
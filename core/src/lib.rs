@@ -0,0 +1,18 @@
+//! Link graphs and the searches over them, shared by the server, its
+//! `wikilinks` CLI and any other tool that loads a graph.
+//!
+//! Articles are numbered, and a [`LinkGraph`] holds the links between the
+//! numbers. Titles and scores are left to the user; a [`Source`] says where a
+//! graph comes from and [`load_snapshot`] reads the snapshot files.
+
+mod graph;
+mod path;
+mod search;
+mod source;
+mod tree;
+
+pub use graph::LinkGraph;
+pub use path::reconstruct;
+pub use search::{BreadthFirst, Bidirectional, DepthFirst, Search};
+pub use source::{escape, load_snapshot, unescape, ArticleRow, Snapshot, Source, DEFAULT_DATABASE_URL};
+pub use tree::{PathTree, UNREACHED};
@@ -0,0 +1,29 @@
+/// The path from `start` to `end` found by following `parent` back from `end`,
+/// as article ids from `start` to `end`. `parent` must lead back to `start`.
+pub fn reconstruct(start: usize, end: usize, parent: impl Fn(usize) -> usize) -> Vec<usize> {
+    let mut curr = end;
+    let mut path = vec![curr];
+    while curr != start {
+        curr = parent(curr);
+        path.push(curr);
+    }
+
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_parents_to_start() {
+        let parent = [0, 0, 1, 2];
+        assert_eq!(reconstruct(0, 3, |id| parent[id]), vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn start_is_its_own_path() {
+        assert_eq!(reconstruct(5, 5, |_| unreachable!()), vec![5]);
+    }
+}
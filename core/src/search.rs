@@ -0,0 +1,213 @@
+use std::collections::VecDeque;
+use crate::graph::LinkGraph;
+use crate::path::reconstruct;
+
+/// A way of finding a path between two articles.
+pub trait Search {
+    /// A path from `start` to `end` as article ids, both included, or `None`
+    /// when `end` can't be reached.
+    fn path(&self, graph: &LinkGraph, start: usize, end: usize) -> Option<Vec<usize>>;
+}
+
+/// Breadth-first search, finds a shortest path.
+pub struct BreadthFirst;
+
+/// Depth-first search, finds some path, usually a long one.
+pub struct DepthFirst;
+
+/// Breadth-first search from both ends at once, along links from `start` and
+/// backlinks from `end`, growing the smaller side. Finds a shortest path while
+/// visiting far fewer articles than [`BreadthFirst`] on large graphs.
+pub struct Bidirectional;
+
+impl BreadthFirst {
+    /// A shortest path from `start` to the nearest article `is_target` accepts.
+    pub fn nearest(&self, graph: &LinkGraph, start: usize, is_target: impl Fn(usize) -> bool) -> Option<Vec<usize>> {
+        let mut queue = VecDeque::<usize>::with_capacity(graph.len());
+        let mut visited = vec![false; graph.len()];
+        let mut predecessor = vec![0usize; graph.len()];
+
+        queue.push_back(start);
+        visited[start] = true;
+
+        while let Some(curr) = queue.pop_front() {
+            if is_target(curr) {
+                return Some(reconstruct(start, curr, |id| predecessor[id]));
+            }
+
+            for &link in &graph.articles[curr] {
+                if visited[link] { continue; }
+
+                predecessor[link] = curr;
+                visited[link] = true;
+                queue.push_back(link);
+            }
+        }
+
+        None
+    }
+}
+
+impl Search for BreadthFirst {
+    fn path(&self, graph: &LinkGraph, start: usize, end: usize) -> Option<Vec<usize>> {
+        self.nearest(graph, start, |id| id == end)
+    }
+}
+
+impl Search for DepthFirst {
+    fn path(&self, graph: &LinkGraph, start: usize, end: usize) -> Option<Vec<usize>> {
+        let mut stack = Vec::<usize>::with_capacity(graph.len());
+        let mut visited = vec![false; graph.len()];
+        let mut predecessor = vec![0usize; graph.len()];
+
+        stack.push(start);
+        visited[start] = true;
+
+        while let Some(curr) = stack.pop() {
+            if curr == end {
+                return Some(reconstruct(start, end, |id| predecessor[id]));
+            }
+
+            for &link in &graph.articles[curr] {
+                if visited[link] { continue; }
+
+                predecessor[link] = curr;
+                visited[link] = true;
+                stack.push(link);
+            }
+        }
+
+        None
+    }
+}
+
+impl Search for Bidirectional {
+    fn path(&self, graph: &LinkGraph, start: usize, end: usize) -> Option<Vec<usize>> {
+        const UNSEEN: usize = usize::MAX;
+
+        if start == end { return Some(vec![start]); }
+
+        // distance from start and the article before, distance to end and the article after
+        let mut forward = vec![(UNSEEN, 0usize); graph.len()];
+        let mut backward = vec![(UNSEEN, 0usize); graph.len()];
+        forward[start] = (0, start);
+        backward[end] = (0, end);
+
+        let mut forward_frontier = vec![start];
+        let mut backward_frontier = vec![end];
+
+        while !forward_frontier.is_empty() && !backward_frontier.is_empty() {
+            let grow_forward = forward_frontier.len() <= backward_frontier.len();
+            let (frontier, seen, other, links) = match grow_forward {
+                true => (&mut forward_frontier, &mut forward, &backward, &graph.articles),
+                false => (&mut backward_frontier, &mut backward, &forward, &graph.backlinks),
+            };
+
+            // grow one whole level, then take the shortest of the paths it closed
+            let mut next = vec![];
+            let mut meeting = None::<(usize, usize)>;
+
+            for &curr in frontier.iter() {
+                for &link in &links[curr] {
+                    if seen[link].0 != UNSEEN { continue; }
+
+                    seen[link] = (seen[curr].0 + 1, curr);
+                    next.push(link);
+
+                    if other[link].0 != UNSEEN {
+                        let length = seen[link].0 + other[link].0;
+                        if meeting.is_none_or(|(best, _)| length < best) {
+                            meeting = Some((length, link));
+                        }
+                    }
+                }
+            }
+
+            if let Some((_, meet)) = meeting {
+                let mut path = reconstruct(start, meet, |id| forward[id].1);
+                let mut curr = meet;
+                while curr != end {
+                    curr = backward[curr].1;
+                    path.push(curr);
+                }
+                return Some(path);
+            }
+
+            *frontier = next;
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0 → 1 → 2 → 3, with a shortcut 0 → 4 → 3 and 5 linking to nothing.
+    fn diamond() -> LinkGraph {
+        LinkGraph::new(vec![vec![1, 4], vec![2], vec![3], vec![], vec![3], vec![]])
+    }
+
+    /// A directed cycle of `n` articles with a chord from every fifth article.
+    fn cycle(n: usize) -> LinkGraph {
+        LinkGraph::new((0..n).map(|i| if i % 5 == 0 { vec![(i + 1) % n, (i * 7 + 3) % n] } else { vec![(i + 1) % n] }).collect())
+    }
+
+    fn is_path(graph: &LinkGraph, path: &[usize], start: usize, end: usize) -> bool {
+        path.first() == Some(&start) && path.last() == Some(&end) && path.windows(2).all(|w| graph.has_edge(w[0], w[1]))
+    }
+
+    #[test]
+    fn breadth_first_finds_shortest_path() {
+        assert_eq!(BreadthFirst.path(&diamond(), 0, 3), Some(vec![0, 4, 3]));
+    }
+
+    #[test]
+    fn no_path_is_none() {
+        let graph = diamond();
+
+        for search in [&BreadthFirst as &dyn Search, &DepthFirst, &Bidirectional] {
+            assert_eq!(search.path(&graph, 3, 0), None);
+            assert_eq!(search.path(&graph, 0, 5), None);
+        }
+    }
+
+    #[test]
+    fn start_is_a_path_to_itself() {
+        let graph = diamond();
+
+        for search in [&BreadthFirst as &dyn Search, &DepthFirst, &Bidirectional] {
+            assert_eq!(search.path(&graph, 2, 2), Some(vec![2]));
+        }
+    }
+
+    #[test]
+    fn depth_first_finds_a_path() {
+        let graph = cycle(50);
+        let path = DepthFirst.path(&graph, 3, 40).unwrap();
+
+        assert!(is_path(&graph, &path, 3, 40));
+    }
+
+    #[test]
+    fn bidirectional_is_as_short_as_breadth_first() {
+        let graph = cycle(50);
+
+        for (start, end) in [(0, 49), (3, 40), (17, 2), (45, 44), (10, 11)] {
+            let shortest = BreadthFirst.path(&graph, start, end).unwrap();
+            let path = Bidirectional.path(&graph, start, end).unwrap();
+
+            assert!(is_path(&graph, &path, start, end));
+            assert_eq!(path.len(), shortest.len(), "{} -> {}", start, end);
+        }
+    }
+
+    #[test]
+    fn nearest_stops_at_closest_target() {
+        let graph = diamond();
+
+        assert_eq!(BreadthFirst.nearest(&graph, 0, |id| id == 2 || id == 4), Some(vec![0, 4]));
+        assert_eq!(BreadthFirst.nearest(&graph, 0, |id| id == 5), None);
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::VecDeque;
+use crate::graph::LinkGraph;
+use crate::path::reconstruct;
+
+/// Marks articles a [`PathTree`] doesn't reach.
+pub const UNREACHED: u32 = u32::MAX;
+
+/// Shortest paths from one article to every article it reaches: the
+/// predecessor tree of a full breadth-first search.
+pub struct PathTree {
+    pub start: usize,
+    /// The article before each one on a shortest path from `start`.
+    pub parent: Vec<u32>,
+    /// Links from `start`, `UNREACHED` for articles it can't reach.
+    pub distance: Vec<u32>,
+    /// Reached articles in the order they were found, nearest first. Every
+    /// article comes after its parent.
+    pub order: Vec<usize>,
+}
+
+impl PathTree {
+    pub fn new(graph: &LinkGraph, start: usize) -> PathTree {
+        let size = graph.len();
+        let mut parent = vec![UNREACHED; size];
+        let mut distance = vec![UNREACHED; size];
+        let mut order = vec![];
+        let mut queue = VecDeque::new();
+
+        distance[start] = 0;
+        parent[start] = start as u32;
+        queue.push_back(start);
+
+        while let Some(curr) = queue.pop_front() {
+            order.push(curr);
+
+            for &link in &graph.articles[curr] {
+                if distance[link] != UNREACHED { continue; }
+
+                distance[link] = distance[curr] + 1;
+                parent[link] = curr as u32;
+                queue.push_back(link);
+            }
+        }
+
+        PathTree { start, parent, distance, order }
+    }
+
+    /// The path from `start` to `end`, `None` if it isn't reached.
+    pub fn path_to(&self, end: usize) -> Option<Vec<usize>> {
+        if *self.distance.get(end)? == UNREACHED { return None; }
+
+        Some(reconstruct(self.start, end, |id| self.parent[id] as usize))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_and_paths() {
+        // 0 → 1 → 2, 0 → 2, 3 → 0
+        let graph = LinkGraph::new(vec![vec![1, 2], vec![2], vec![], vec![0]]);
+        let tree = PathTree::new(&graph, 0);
+
+        assert_eq!(tree.distance, vec![0, 1, 1, UNREACHED]);
+        assert_eq!(tree.order, vec![0, 1, 2]);
+        assert_eq!(tree.path_to(2), Some(vec![0, 2]));
+        assert_eq!(tree.path_to(3), None);
+        assert_eq!(tree.path_to(0), Some(vec![0]));
+    }
+}
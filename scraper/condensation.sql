@@ -1 +1 @@
-UPDATE articles SET condensed_links = (SELECT id FROM articles art WHERE art.title = any (links));
\ No newline at end of file
+UPDATE articles SET condensed_links = ARRAY(SELECT art.id FROM articles art WHERE art.wiki = articles.wiki AND art.title = any (articles.links));
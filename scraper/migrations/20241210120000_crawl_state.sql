@@ -0,0 +1,30 @@
+-- crawl state for the links scraper
+-- Previously the links scraper picked its work with `cardinality(links) = 0`,
+-- which re-fetched articles that really have no links on every restart.
+
+CREATE TABLE public.crawl_state (
+                                    title text NOT NULL,
+                                    status text NOT NULL DEFAULT 'pending',
+                                    attempts integer NOT NULL DEFAULT 0,
+                                    last_error text,
+                                    next_attempt_at timestamp with time zone NOT NULL DEFAULT now(),
+                                    updated_at timestamp with time zone NOT NULL DEFAULT now(),
+                                    CONSTRAINT crawl_state_status_check CHECK (status IN ('pending', 'done', 'failed'))
+);
+
+ALTER TABLE public.crawl_state OWNER TO postgres;
+
+ALTER TABLE ONLY public.crawl_state
+    ADD CONSTRAINT crawl_state_pkey PRIMARY KEY (title);
+
+ALTER TABLE ONLY public.crawl_state
+    ADD CONSTRAINT crawl_state_title_fkey FOREIGN KEY (title) REFERENCES public.articles(title) ON DELETE CASCADE;
+
+-- work is picked by status and retry time
+CREATE INDEX crawl_state_status_idx ON public.crawl_state USING btree (status, next_attempt_at);
+
+-- articles that already have links were crawled by an earlier run
+INSERT INTO public.crawl_state (title, status)
+SELECT title, CASE WHEN cardinality(links) > 0 THEN 'done' ELSE 'pending' END
+FROM public.articles
+ON CONFLICT DO NOTHING;
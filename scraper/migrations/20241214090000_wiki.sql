@@ -0,0 +1,39 @@
+-- wiki identifier on every row
+-- The scrapers can crawl any MediaWiki instance, so several graphs
+-- (e.g. enwiki, dewiki, a local wiki) can live in the same database.
+-- Everything crawled before this migration came from English Wikipedia.
+
+ALTER TABLE public.articles ADD COLUMN wiki text NOT NULL DEFAULT 'enwiki';
+ALTER TABLE public.crawl_state ADD COLUMN wiki text NOT NULL DEFAULT 'enwiki';
+ALTER TABLE public.requests ADD COLUMN wiki text NOT NULL DEFAULT 'enwiki';
+
+-- the articles generator walks one namespace at a time
+ALTER TABLE public.requests ADD COLUMN namespace integer NOT NULL DEFAULT 0;
+
+-- new rows must say which wiki they belong to
+ALTER TABLE public.articles ALTER COLUMN wiki DROP DEFAULT;
+ALTER TABLE public.crawl_state ALTER COLUMN wiki DROP DEFAULT;
+ALTER TABLE public.requests ALTER COLUMN wiki DROP DEFAULT;
+ALTER TABLE public.requests ALTER COLUMN namespace DROP DEFAULT;
+
+-- titles are only unique within a wiki
+
+ALTER TABLE ONLY public.crawl_state DROP CONSTRAINT crawl_state_title_fkey;
+ALTER TABLE ONLY public.crawl_state DROP CONSTRAINT crawl_state_pkey;
+ALTER TABLE ONLY public.articles DROP CONSTRAINT articles_pkey;
+
+ALTER TABLE ONLY public.articles
+    ADD CONSTRAINT articles_pkey PRIMARY KEY (wiki, title);
+
+ALTER TABLE ONLY public.crawl_state
+    ADD CONSTRAINT crawl_state_pkey PRIMARY KEY (wiki, title);
+
+ALTER TABLE ONLY public.crawl_state
+    ADD CONSTRAINT crawl_state_title_fkey FOREIGN KEY (wiki, title) REFERENCES public.articles(wiki, title) ON DELETE CASCADE;
+
+DROP INDEX public.crawl_state_status_idx;
+CREATE INDEX crawl_state_status_idx ON public.crawl_state USING btree (wiki, status, next_attempt_at);
+
+-- the server loads one wiki's graph ordered by id
+CREATE INDEX articles_wiki_id_idx ON public.articles USING btree (wiki, id);
+CREATE INDEX requests_wiki_namespace_idx ON public.requests USING btree (wiki, namespace, created_at);
@@ -0,0 +1,47 @@
+-- incremental updates from the recent changes feed
+
+-- position in each wiki's recent changes feed, so polling resumes where it stopped
+CREATE TABLE public.rc_state (
+                                 wiki text NOT NULL,
+                                 rc_timestamp text NOT NULL,
+                                 rcid bigint NOT NULL,
+                                 updated_at timestamp with time zone NOT NULL DEFAULT now()
+);
+
+ALTER TABLE public.rc_state OWNER TO postgres;
+
+ALTER TABLE ONLY public.rc_state
+    ADD CONSTRAINT rc_state_pkey PRIMARY KEY (wiki);
+
+-- every change to an article's condensed links, in order, so a running server can
+-- apply them to its graph. 'upsert' rows carry the article's new title and links,
+-- 'delete' rows remove the article.
+CREATE TABLE public.graph_changes (
+                                      seq bigserial NOT NULL,
+                                      wiki text NOT NULL,
+                                      kind text NOT NULL,
+                                      article_id integer NOT NULL,
+                                      title text NOT NULL,
+                                      links integer[] NOT NULL,
+                                      created_at timestamp with time zone NOT NULL DEFAULT now(),
+                                      CONSTRAINT graph_changes_kind_check CHECK (kind IN ('upsert', 'delete'))
+);
+
+ALTER TABLE public.graph_changes OWNER TO postgres;
+
+ALTER TABLE ONLY public.graph_changes
+    ADD CONSTRAINT graph_changes_pkey PRIMARY KEY (seq);
+
+CREATE INDEX graph_changes_wiki_seq_idx ON public.graph_changes USING btree (wiki, seq);
+
+-- find the articles linking to a title that was created, deleted or moved
+CREATE INDEX articles_links_idx ON public.articles USING gin (links);
+
+-- moved articles keep their id and crawl state
+ALTER TABLE ONLY public.crawl_state DROP CONSTRAINT crawl_state_title_fkey;
+
+ALTER TABLE ONLY public.crawl_state
+    ADD CONSTRAINT crawl_state_title_fkey FOREIGN KEY (wiki, title) REFERENCES public.articles(wiki, title) ON UPDATE CASCADE ON DELETE CASCADE;
+
+-- new articles get their condensed links from the changes scraper
+ALTER TABLE ONLY public.articles ALTER COLUMN condensed_links SET DEFAULT '{}';
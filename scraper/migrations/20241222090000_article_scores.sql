@@ -0,0 +1,16 @@
+-- centrality scores computed by the server over its in-memory graph.
+-- The server replaces every row of a wiki when it computes them again.
+
+CREATE TABLE public.article_scores (
+                                       wiki text NOT NULL,
+                                       article_id integer NOT NULL,
+                                       pagerank double precision NOT NULL,
+                                       in_degree integer NOT NULL,
+                                       betweenness double precision NOT NULL,
+                                       computed_at timestamp with time zone NOT NULL DEFAULT now()
+);
+
+ALTER TABLE public.article_scores OWNER TO postgres;
+
+ALTER TABLE ONLY public.article_scores
+    ADD CONSTRAINT article_scores_pkey PRIMARY KEY (wiki, article_id);
@@ -0,0 +1,6 @@
+-- community of each article, found by label propagation in the server along
+-- with the scores. -1 for rows stored before clusters were computed.
+
+ALTER TABLE ONLY public.article_scores ADD COLUMN cluster integer NOT NULL DEFAULT -1;
+
+CREATE INDEX article_scores_cluster_idx ON public.article_scores USING btree (wiki, cluster);
@@ -0,0 +1,34 @@
+-- categories of every article and the category hierarchy, fetched by the
+-- `categories` scraper with `prop=categories`. Titles are stored as the wiki
+-- returns them, with the namespace prefix (e.g. `Category:Physics`).
+-- Hidden maintenance categories are left out.
+
+CREATE TABLE public.article_categories (
+                                           wiki text NOT NULL,
+                                           title text NOT NULL,
+                                           categories text[] NOT NULL DEFAULT '{}',
+                                           fetched_at timestamp with time zone NOT NULL DEFAULT now()
+);
+
+ALTER TABLE public.article_categories OWNER TO postgres;
+
+ALTER TABLE ONLY public.article_categories
+    ADD CONSTRAINT article_categories_pkey PRIMARY KEY (wiki, title);
+
+ALTER TABLE ONLY public.article_categories
+    ADD CONSTRAINT article_categories_title_fkey FOREIGN KEY (wiki, title) REFERENCES public.articles(wiki, title) ON UPDATE CASCADE ON DELETE CASCADE;
+
+-- the parent categories of every category page, one row per category that
+-- was fetched. Categories without a page have no parents.
+
+CREATE TABLE public.categories (
+                                   wiki text NOT NULL,
+                                   title text NOT NULL,
+                                   parents text[] NOT NULL DEFAULT '{}',
+                                   fetched_at timestamp with time zone NOT NULL DEFAULT now()
+);
+
+ALTER TABLE public.categories OWNER TO postgres;
+
+ALTER TABLE ONLY public.categories
+    ADD CONSTRAINT categories_pkey PRIMARY KEY (wiki, title);
@@ -2,7 +2,8 @@ mod dto;
 
 use dto::*;
 
-use reqwest::Url;
+use scraper::client::{Client, ClientConfig};
+use scraper::wiki::WikiConfig;
 use sqlx::postgres::PgPoolOptions;
 use sqlx::{query, PgPool};
 
@@ -19,49 +20,71 @@ async fn main() {
         .await
         .expect("Failed to connect to database");
 
-    // select the most recent request so we can continue with the three continue params
-    let existing_continue = query!("select continue as cont, pl_continue, gap_continue from requests order by created_at desc limit 1")
-        .fetch_optional(&pool)
-        .await
-        .expect("Failed to fetch requests");
-
-    // continue params for first request = existing_continue or None for all three params
-    let mut r#continue = existing_continue
-        .map(|c| PageLinksContinue {
-            gap_continue: c.gap_continue,
-            pl_continue: c.pl_continue,
-            r#continue: c.cont,
-        })
-        .unwrap_or(PageLinksContinue { gap_continue: None, pl_continue: None, r#continue: None });
-
-    // shared client to avoid overloading DNS server
-    let client = reqwest::Client::new();
-
-    loop {
-        // fetch article titles
-        let Ok(r) = fetch(&client, &pool, r#continue.clone()).await else { continue };
-        let cont = r.1.unwrap_or(PageLinksContinue { gap_continue: None, pl_continue: None, r#continue: None });
-        if r.0 {
-            println!("Done");
-            break;
-        }
+    let wiki = WikiConfig::from_env();
+    println!("Crawling articles of {} from {}", wiki.id, wiki.api_url);
+
+    // shared client to avoid overloading DNS server, rate limited and
+    // retrying on its own when the API is lagged or failing
+    let client = Client::new(ClientConfig::from_env());
 
-        // save continue values for next request or reuse values
-        r#continue =  PageLinksContinue {
-            gap_continue: cont.gap_continue.clone().or_else(|| r#continue.gap_continue.clone()),
-            pl_continue: cont.pl_continue.clone(),
-            r#continue: cont.r#continue.clone(),
-        };
+    // the allpages generator walks a single namespace, so each namespace
+    // is crawled in turn with its own continue params
+    for &namespace in &wiki.namespaces {
+        // select the most recent request so we can continue with the three continue params
+        let existing_continue = query!("select continue as cont, pl_continue, gap_continue from requests where wiki = $1 and namespace = $2 order by created_at desc limit 1", wiki.id, namespace)
+            .fetch_optional(&pool)
+            .await
+            .expect("Failed to fetch requests");
+
+        // continue params for first request = existing_continue or None for all three params
+        let mut r#continue = existing_continue
+            .map(|c| PageLinksContinue {
+                gap_continue: c.gap_continue,
+                pl_continue: c.pl_continue,
+                r#continue: c.cont,
+            })
+            .unwrap_or(PageLinksContinue { gap_continue: None, pl_continue: None, r#continue: None });
+
+        loop {
+            // fetch article titles
+            let Ok(r) = fetch(&client, &pool, &wiki, namespace, r#continue.clone()).await else { continue };
+            if r.0 {
+                println!("Done with namespace {}", namespace);
+                break;
+            }
+            let cont = r.1.unwrap_or(PageLinksContinue { gap_continue: None, pl_continue: None, r#continue: None });
+
+            // save continue values for next request or reuse values
+            r#continue =  PageLinksContinue {
+                gap_continue: cont.gap_continue.clone().or_else(|| r#continue.gap_continue.clone()),
+                pl_continue: cont.pl_continue.clone(),
+                r#continue: cont.r#continue.clone(),
+            };
+        }
     }
+
+    println!("Done");
 }
 
-pub async fn fetch(client: &reqwest::Client, pool: &PgPool, r#continue: PageLinksContinue) -> Result<(bool, Option<PageLinksContinue>), ()> {
-    let mut url = Url::parse("https://en.wikipedia.org/w/api.php?action=query&format=json&generator=allpages&formatversion=2&plnamespace=0&pllimit=max&gapnamespace=0&gaplimit=max&gapdir=ascending&gapfilterredir=nonredirects").unwrap();
+pub async fn fetch(client: &Client, pool: &PgPool, wiki: &WikiConfig, namespace: i32, r#continue: PageLinksContinue) -> Result<(bool, Option<PageLinksContinue>), ()> {
+    let namespace_param = namespace.to_string();
+    let mut url = wiki.url(&[
+        ("action", "query"),
+        ("format", "json"),
+        ("generator", "allpages"),
+        ("formatversion", "2"),
+        ("plnamespace", &wiki.namespace_param()),
+        ("pllimit", "max"),
+        ("gapnamespace", &namespace_param),
+        ("gaplimit", "max"),
+        ("gapdir", "ascending"),
+        ("gapfilterredir", "nonredirects"),
+    ]);
 
     // continue parameters to get the next page of results
 
     if let Some(ref cont) = r#continue.clone().r#continue {
-        url.query_pairs_mut().append_pair("continue", &cont);
+        url.query_pairs_mut().append_pair("continue", cont);
     }
     if let Some(ref gap_continue) = r#continue.clone().gap_continue {
         url.query_pairs_mut().append_pair("gapcontinue", gap_continue);
@@ -72,13 +95,15 @@ pub async fn fetch(client: &reqwest::Client, pool: &PgPool, r#continue: PageLink
 
     print!("Sending request cont={:?}...", r#continue);
 
-    let resp = match client.get(url)
-        .header("User-Agent", "qwright10; wrightq00@gmail.com / research use")
-        .send()
-        .await
-        .unwrap()
-        .json::<PageLinks>()
-        .await {
+    let resp = match client.get(url).await {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(())
+        }
+    };
+
+    let resp = match serde_json::from_str::<PageLinks>(&resp) {
         Ok(r) => r,
         Err(_) => {
             eprintln!("Malformed response!");
@@ -88,15 +113,18 @@ pub async fn fetch(client: &reqwest::Client, pool: &PgPool, r#continue: PageLink
 
     println!("Done");
 
+    // the generator is exhausted once there is nothing left to continue
+    let done = resp.r#continue.is_none();
+
     // some pages will have no articles, but we should still continue
     let Some(links) = resp.query
         .and_then(|mut m| m.remove("pages")) else {
         eprintln!("Response missing pages!");
-        return Ok((false, resp.r#continue));
+        return Ok((done, resp.r#continue));
     };
 
     // add successful request to requests table
-    let _ = query!("INSERT INTO requests (pl_continue, gap_continue, continue) VALUES ($1, $2, $3)", r#continue.pl_continue, r#continue.gap_continue, r#continue.r#continue)
+    let _ = query!("INSERT INTO requests (wiki, namespace, pl_continue, gap_continue, continue) VALUES ($1, $2, $3, $4, $5)", wiki.id, namespace, r#continue.pl_continue, r#continue.gap_continue, r#continue.r#continue)
         .execute(pool)
         .await
         .map_err(|_| {
@@ -112,11 +140,17 @@ pub async fn fetch(client: &reqwest::Client, pool: &PgPool, r#continue: PageLink
             .collect::<Vec<_>>();
 
         // store article title
-        query!("INSERT INTO articles(title, links) VALUES ($1, $2) on conflict do nothing", title, l.as_slice())
+        query!("INSERT INTO articles(wiki, title, links) VALUES ($1, $2, $3) on conflict do nothing", wiki.id, title, l.as_slice())
             .execute(pool)
             .await
             .expect("Failed to insert article");
+
+        // queue the article for the links scraper
+        query!("INSERT INTO crawl_state(wiki, title) VALUES ($1, $2) on conflict do nothing", wiki.id, title)
+            .execute(pool)
+            .await
+            .expect("Failed to queue article");
     }
 
-    Ok((false, resp.r#continue))
+    Ok((done, resp.r#continue))
 }
\ No newline at end of file
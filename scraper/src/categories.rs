@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use crate::client::Client;
+use crate::wiki::WikiConfig;
+
+#[derive(Deserialize, Debug)]
+pub struct CategoriesContinue {
+    #[serde(rename = "clcontinue")]
+    pub cl_continue: Option<String>,
+
+    #[serde(rename = "continue")]
+    pub r#continue: Option<String>
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CategoriesPage {
+    pub title: String,
+    pub categories: Option<Vec<Category>>,
+
+    /// set for titles that do not exist (anymore) on the wiki
+    #[serde(default)]
+    pub missing: bool
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Category {
+    pub title: String
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Categories {
+    #[serde(rename = "continue")]
+    pub r#continue: Option<CategoriesContinue>,
+    pub query: Option<HashMap<String, Vec<CategoriesPage>>>
+}
+
+/// Request the categories of every title in `titles` (at most 50), following
+/// continue parameters like [`crate::pagelinks::fetch_links`]. Works for
+/// articles and category pages alike; the categories of a category page are
+/// its parents. Hidden categories are left out, and so are titles that do not
+/// exist on the wiki.
+pub async fn fetch_categories(client: &Client, wiki: &WikiConfig, titles: &[String]) -> Result<HashMap<String, Vec<String>>, String> {
+    let mut r#continue = CategoriesContinue { cl_continue: None, r#continue: None };
+    let mut collected = HashMap::<String, Vec<String>>::new();
+
+    loop {
+        let mut url = wiki.url(&[
+            ("action", "query"),
+            ("format", "json"),
+            ("prop", "categories"),
+            ("formatversion", "2"),
+            ("cllimit", "max"),
+            ("clshow", "!hidden"),
+            ("titles", &titles.join("|")),
+        ]);
+
+        let CategoriesContinue { r#continue: cont, cl_continue } = r#continue;
+
+        if let Some(ref cl_continue) = cl_continue {
+            url.query_pairs_mut().append_pair("clcontinue", cl_continue.as_str());
+        }
+
+        if let Some(ref cont) = cont {
+            url.query_pairs_mut().append_pair("continue", cont.as_str());
+        }
+
+        println!("Sending request cont={:?} @ {}...", cl_continue, titles[0]);
+
+        let resp = client.get(url)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let resp: Categories = serde_json::from_str(&resp)
+            .map_err(|e| format!("malformed response: {}", e))?;
+
+        let Some(pages) = resp.query.and_then(|mut q| q.remove("pages")) else { break };
+
+        for page in pages {
+            if page.missing { continue; }
+
+            let c = page.categories
+                .unwrap_or_default()
+                .into_iter()
+                .map(|c| c.title);
+
+            collected.entry(page.title).or_default().extend(c);
+        }
+
+        match resp.r#continue {
+            Some(cont) => r#continue = cont,
+            None => break,
+        }
+    }
+
+    Ok(collected)
+}
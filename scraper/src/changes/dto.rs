@@ -0,0 +1,56 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RecentChange {
+    /// edit, new or log
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub ns: i32,
+    pub title: String,
+
+    #[serde(default)]
+    pub rcid: i64,
+
+    #[serde(default)]
+    pub timestamp: String,
+
+    pub logtype: Option<String>,
+    pub logaction: Option<String>,
+    pub logparams: Option<LogParams>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LogParams {
+    /// set for moves
+    pub target_ns: Option<i32>,
+    pub target_title: Option<String>
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RecentChangesContinue {
+    #[serde(rename = "rccontinue")]
+    pub rc_continue: Option<String>,
+
+    #[serde(rename = "continue")]
+    pub r#continue: Option<String>
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RecentChangesQuery {
+    pub recentchanges: Vec<RecentChange>
+}
+
+#[derive(Deserialize, Debug)]
+pub struct RecentChanges {
+    #[serde(rename = "continue")]
+    pub r#continue: Option<RecentChangesContinue>,
+    pub query: Option<RecentChangesQuery>
+}
+
+/// A recent changes log to replay: either a list of changes or a saved API response.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum ReplayLog {
+    Changes(Vec<RecentChange>),
+    Response(RecentChanges)
+}
@@ -0,0 +1,147 @@
+use crate::dto::RecentChange;
+use std::collections::BTreeSet;
+
+/// What has to happen to the stored graph for a run of recent changes.
+/// Moves are applied first, then deletions, then the refetches.
+#[derive(Debug, Default, PartialEq)]
+pub struct Plan {
+    /// (old title, new title), in the order they happened
+    pub moved: Vec<(String, String)>,
+    pub deleted: BTreeSet<String>,
+    /// titles whose links have to be fetched again, created ones included
+    pub refetch: BTreeSet<String>,
+}
+
+/// Collapse changes (oldest first) into a plan. Several edits of one article
+/// only fetch its links once, and an article deleted after an edit is not
+/// fetched at all. Changes outside the crawled namespaces are ignored.
+pub fn plan(changes: &[RecentChange], namespaces: &[i32]) -> Plan {
+    let mut plan = Plan::default();
+
+    for change in changes {
+        if !namespaces.contains(&change.ns) { continue; }
+
+        let title = change.title.clone();
+
+        match (change.kind.as_str(), change.logtype.as_deref(), change.logaction.as_deref()) {
+            ("edit" | "new", _, _) | ("log", Some("delete"), Some("restore")) => {
+                plan.deleted.remove(&title);
+                plan.refetch.insert(title);
+            }
+
+            ("log", Some("delete"), Some("delete" | "delete_redir")) => {
+                plan.refetch.remove(&title);
+                plan.deleted.insert(title);
+            }
+
+            ("log", Some("move"), _) => {
+                let Some(params) = &change.logparams else { continue };
+                let Some(target) = params.target_title.clone() else { continue };
+
+                plan.refetch.remove(&title);
+
+                // moved out of the crawled namespaces, the article is gone
+                if !namespaces.contains(&params.target_ns.unwrap_or(change.ns)) {
+                    plan.deleted.insert(title);
+                    continue;
+                }
+
+                // the old title is a redirect now, the article lives on under the new one
+                plan.deleted.remove(&target);
+                plan.refetch.insert(target.clone());
+                plan.moved.push((title, target));
+            }
+
+            _ => {}
+        }
+    }
+
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Changes as the API returns them, oldest first.
+    fn changes(changes: serde_json::Value) -> Vec<RecentChange> {
+        serde_json::from_value(changes).unwrap()
+    }
+
+    fn edit(title: &str) -> serde_json::Value {
+        json!({ "type": "edit", "ns": 0, "title": title })
+    }
+
+    fn log(logtype: &str, logaction: &str, title: &str) -> serde_json::Value {
+        json!({ "type": "log", "ns": 0, "title": title, "logtype": logtype, "logaction": logaction })
+    }
+
+    fn moved(from: &str, to: &str, target_ns: i32) -> serde_json::Value {
+        json!({
+            "type": "log", "ns": 0, "title": from, "logtype": "move", "logaction": "move",
+            "logparams": { "target_ns": target_ns, "target_title": to },
+        })
+    }
+
+    fn titles(titles: &[&str]) -> BTreeSet<String> {
+        titles.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn fetches_edited_and_created_articles_once() {
+        let changes = changes(json!([edit("A"), edit("A"), { "type": "new", "ns": 0, "title": "B" }, edit("A")]));
+        let plan = plan(&changes, &[0]);
+
+        assert_eq!(plan, Plan { refetch: titles(&["A", "B"]), ..Plan::default() });
+    }
+
+    #[test]
+    fn the_last_of_delete_and_restore_wins() {
+        let deleted = plan(&changes(json!([edit("A"), log("delete", "delete", "A")])), &[0]);
+        assert_eq!(deleted, Plan { deleted: titles(&["A"]), ..Plan::default() });
+
+        let restored = plan(&changes(json!([log("delete", "delete", "A"), log("delete", "restore", "A")])), &[0]);
+        assert_eq!(restored, Plan { refetch: titles(&["A"]), ..Plan::default() });
+    }
+
+    #[test]
+    fn moves_are_kept_in_order_and_fetch_only_the_last_title() {
+        let changes = changes(json!([edit("A"), moved("A", "B", 0), edit("B"), moved("B", "C", 0)]));
+        let plan = plan(&changes, &[0]);
+
+        assert_eq!(plan.moved, vec![("A".to_string(), "B".to_string()), ("B".to_string(), "C".to_string())]);
+        assert_eq!(plan.refetch, titles(&["C"]));
+        assert!(plan.deleted.is_empty());
+    }
+
+    #[test]
+    fn a_move_over_a_deleted_article_brings_the_title_back() {
+        let changes = changes(json!([log("delete", "delete", "B"), moved("A", "B", 0)]));
+        let plan = plan(&changes, &[0]);
+
+        assert_eq!(plan.moved, vec![("A".to_string(), "B".to_string())]);
+        assert!(plan.deleted.is_empty());
+        assert_eq!(plan.refetch, titles(&["B"]));
+    }
+
+    #[test]
+    fn a_move_out_of_the_namespaces_deletes_the_article() {
+        let changes = changes(json!([edit("A"), moved("A", "User:A", 2)]));
+        let plan = plan(&changes, &[0]);
+
+        assert_eq!(plan, Plan { deleted: titles(&["A"]), ..Plan::default() });
+    }
+
+    #[test]
+    fn ignores_other_namespaces_and_log_types() {
+        let changes = changes(json!([
+            { "type": "edit", "ns": 1, "title": "Talk:A" },
+            log("protect", "protect", "A"),
+            log("delete", "delete", "B"),
+        ]));
+
+        assert_eq!(plan(&changes, &[0]), Plan { deleted: titles(&["B"]), ..Plan::default() });
+        assert_eq!(plan(&changes, &[0, 1]), Plan { deleted: titles(&["B"]), refetch: titles(&["Talk:A"]), ..Plan::default() });
+    }
+}
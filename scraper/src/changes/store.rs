@@ -0,0 +1,160 @@
+use sqlx::{query, PgConnection, PgPool};
+
+// Every function here keeps `condensed_links` up to date for the articles it
+// touches and records their new state in `graph_changes`, in one transaction,
+// so a server applying the changes never sees half of an update.
+
+/// Store the links of an article, creating it if it is new. A new article's
+/// id is added to the condensed links of every article already linking to it.
+pub async fn upsert(pool: &PgPool, wiki: &str, title: &str, links: &[String]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let article = query!(
+        r#"INSERT INTO articles (wiki, title, links, condensed_links)
+           VALUES ($1, $2, $3, ARRAY(SELECT art.id FROM articles art WHERE art.wiki = $1 AND art.title = any ($3)))
+           ON CONFLICT (wiki, title) DO UPDATE SET links = excluded.links, condensed_links = excluded.condensed_links
+           RETURNING id, condensed_links, (xmax = 0) AS "inserted!""#,
+        wiki,
+        title,
+        links
+    )
+        .fetch_one(&mut *tx)
+        .await?;
+
+    query!(
+        "INSERT INTO graph_changes (wiki, kind, article_id, title, links) VALUES ($1, 'upsert', $2, $3, $4)",
+        wiki,
+        article.id,
+        title,
+        &article.condensed_links
+    )
+        .execute(&mut *tx)
+        .await?;
+
+    query!(
+        "INSERT INTO crawl_state (wiki, title, status, updated_at) VALUES ($1, $2, 'done', now())
+         ON CONFLICT (wiki, title) DO UPDATE SET status = 'done', last_error = NULL, updated_at = now()",
+        wiki,
+        title
+    )
+        .execute(&mut *tx)
+        .await?;
+
+    // links to the new article (including its own) can become edges now
+    if article.inserted {
+        relink(&mut tx, wiki, title).await?;
+    }
+
+    tx.commit().await
+}
+
+/// Delete an article and remove it from the condensed links of every article linking to it.
+pub async fn delete(pool: &PgPool, wiki: &str, title: &str) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    delete_article(&mut tx, wiki, title).await?;
+    tx.commit().await
+}
+
+/// Rename an article, keeping its id and links. Articles linking to the old
+/// title lose their edge (it is a redirect now, and redirects are not part of
+/// the graph) and articles linking to the new title gain one. A stale article
+/// at the new title is deleted first, like the wiki does when moving over it.
+/// Returns false if there was no article at the old title.
+pub async fn rename(pool: &PgPool, wiki: &str, old: &str, new: &str) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    delete_article(&mut tx, wiki, new).await?;
+
+    let Some(article) = query!(
+        "UPDATE articles SET title = $3 WHERE wiki = $1 AND title = $2 RETURNING id, condensed_links",
+        wiki,
+        old,
+        new
+    )
+        .fetch_optional(&mut *tx)
+        .await? else {
+        tx.commit().await?;
+        return Ok(false);
+    };
+
+    query!(
+        "INSERT INTO graph_changes (wiki, kind, article_id, title, links) VALUES ($1, 'upsert', $2, $3, $4)",
+        wiki,
+        article.id,
+        new,
+        &article.condensed_links
+    )
+        .execute(&mut *tx)
+        .await?;
+
+    relink(&mut tx, wiki, old).await?;
+    relink(&mut tx, wiki, new).await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+async fn delete_article(conn: &mut PgConnection, wiki: &str, title: &str) -> Result<(), sqlx::Error> {
+    let deleted = query!(
+        "WITH deleted AS (DELETE FROM articles WHERE wiki = $1 AND title = $2 RETURNING wiki, id, title)
+         INSERT INTO graph_changes (wiki, kind, article_id, title, links)
+         SELECT wiki, 'delete', id, title, '{}' FROM deleted",
+        wiki,
+        title
+    )
+        .execute(&mut *conn)
+        .await?;
+
+    if deleted.rows_affected() > 0 {
+        relink(conn, wiki, title).await?;
+    }
+
+    Ok(())
+}
+
+/// Recalculate the condensed links of every article whose links contain `title`,
+/// the same way `condensation.sql` does for the whole table.
+async fn relink(conn: &mut PgConnection, wiki: &str, title: &str) -> Result<(), sqlx::Error> {
+    query!(
+        "WITH changed AS (
+             UPDATE articles a
+             SET condensed_links = ARRAY(SELECT art.id FROM articles art WHERE art.wiki = a.wiki AND art.title = any (a.links))
+             WHERE a.wiki = $1 AND a.links @> ARRAY[$2]
+             RETURNING a.wiki, a.id, a.title, a.condensed_links
+         )
+         INSERT INTO graph_changes (wiki, kind, article_id, title, links)
+         SELECT wiki, 'upsert', id, title, condensed_links FROM changed",
+        wiki,
+        title
+    )
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
+pub struct RcState {
+    pub rc_timestamp: String,
+    pub rcid: i64,
+}
+
+pub async fn load_rc_state(pool: &PgPool, wiki: &str) -> Result<Option<RcState>, sqlx::Error> {
+    query!("SELECT rc_timestamp, rcid FROM rc_state WHERE wiki = $1", wiki)
+        .fetch_optional(pool)
+        .await
+        .map(|r| r.map(|r| RcState { rc_timestamp: r.rc_timestamp, rcid: r.rcid }))
+}
+
+pub async fn save_rc_state(pool: &PgPool, wiki: &str, state: &RcState) -> Result<(), sqlx::Error> {
+    query!(
+        "INSERT INTO rc_state (wiki, rc_timestamp, rcid) VALUES ($1, $2, $3)
+         ON CONFLICT (wiki) DO UPDATE SET rc_timestamp = excluded.rc_timestamp, rcid = excluded.rcid, updated_at = now()",
+        wiki,
+        state.rc_timestamp,
+        state.rcid
+    )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use reqwest::header::RETRY_AFTER;
+use reqwest::{StatusCode, Url};
+use std::fmt;
+use std::time::Duration;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time::Instant;
+
+/// Settings shared by both scrapers for talking to the MediaWiki API.
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// Sustained requests per second across every worker.
+    pub requests_per_second: f64,
+    /// Requests that can be sent back to back before the rate limit kicks in.
+    pub burst: u32,
+    /// Maximum number of requests in flight at once.
+    pub concurrency: usize,
+    /// Retries for a single request before giving up.
+    pub max_retries: u32,
+    /// Sent as the `maxlag` parameter. The API refuses requests while replication
+    /// lag is higher than this many seconds, and we wait and try again.
+    pub maxlag: Option<u32>,
+    /// First retry delay; doubled on every attempt.
+    pub base_backoff: Duration,
+    /// Upper bound for a single retry delay.
+    pub max_backoff: Duration,
+    /// Identifies the scraper to the wiki's operators. Wikimedia wikis require
+    /// contact information in it.
+    pub user_agent: String,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        ClientConfig {
+            requests_per_second: 20.0,
+            burst: 5,
+            concurrency: 5,
+            max_retries: 8,
+            maxlag: Some(5),
+            base_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(120),
+            user_agent: "qwright10 quentin.wright@ufl.edu / research @ University of Florida".to_string(),
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Defaults overridden by the `SCRAPER_RPS`, `SCRAPER_BURST`, `SCRAPER_CONCURRENCY`,
+    /// `SCRAPER_MAX_RETRIES`, `SCRAPER_MAXLAG` and `SCRAPER_USER_AGENT` environment variables.
+    /// `SCRAPER_MAXLAG=0` disables the `maxlag` parameter.
+    pub fn from_env() -> Self {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            std::env::var(name).ok().and_then(|v| v.parse().ok())
+        }
+
+        let mut config = ClientConfig::default();
+
+        if let Some(rps) = var("SCRAPER_RPS") { config.requests_per_second = rps; }
+        if let Some(burst) = var("SCRAPER_BURST") { config.burst = burst; }
+        if let Some(concurrency) = var("SCRAPER_CONCURRENCY") { config.concurrency = concurrency; }
+        if let Some(retries) = var("SCRAPER_MAX_RETRIES") { config.max_retries = retries; }
+        if let Some(maxlag) = var::<u32>("SCRAPER_MAXLAG") { config.maxlag = (maxlag > 0).then_some(maxlag); }
+        if let Ok(user_agent) = std::env::var("SCRAPER_USER_AGENT") { config.user_agent = user_agent; }
+
+        config
+    }
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The request could not be sent or the body could not be read.
+    Request(reqwest::Error),
+    /// The server kept answering with a non-success status.
+    Status(StatusCode),
+    /// The server kept reporting replication lag above `maxlag`.
+    Lagged,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Request(e) => write!(f, "request failed: {}", e),
+            ClientError::Status(s) => write!(f, "server responded with {}", s),
+            ClientError::Lagged => write!(f, "server is lagged"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// Token bucket shared by every request made through a [`Client`].
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    rate: f64,
+    last: Instant,
+    /// Set when the server asks us to back off; nobody sends until then.
+    paused_until: Option<Instant>,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, burst: u32) -> Self {
+        let capacity = f64::from(burst.max(1));
+        TokenBucket { capacity, tokens: capacity, rate, last: Instant::now(), paused_until: None }
+    }
+
+    /// Take a token, or return how long to wait before trying again.
+    fn take(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+
+        if let Some(until) = self.paused_until {
+            if until > now { return Some(until - now); }
+            self.paused_until = None;
+        }
+
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - self.tokens) / self.rate))
+        }
+    }
+
+    fn pause(&mut self, delay: Duration) {
+        let until = Instant::now() + delay;
+        self.paused_until = Some(self.paused_until.map_or(until, |p| p.max(until)));
+    }
+}
+
+/// HTTP client for the MediaWiki API. Requests from every worker share one
+/// rate limit and concurrency limit, and are retried when the API is lagged,
+/// rate limited or failing.
+pub struct Client {
+    http: reqwest::Client,
+    config: ClientConfig,
+    bucket: Mutex<TokenBucket>,
+    permits: Semaphore,
+}
+
+impl Client {
+    pub fn new(config: ClientConfig) -> Self {
+        // a single reqwest Client caches DNS requests and connections. Without it
+        // requests will fail due to DNS server overload, especially if the server
+        // is running on the user's network gateway
+        let http = reqwest::Client::builder()
+            .user_agent(config.user_agent.clone())
+            .build()
+            .expect("Failed to build HTTP client");
+
+        Client {
+            http,
+            bucket: Mutex::new(TokenBucket::new(config.requests_per_second, config.burst)),
+            permits: Semaphore::new(config.concurrency.max(1)),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// Send a GET request and return the response body.
+    pub async fn get(&self, mut url: Url) -> Result<String, ClientError> {
+        if let Some(maxlag) = self.config.maxlag {
+            url.query_pairs_mut().append_pair("maxlag", &maxlag.to_string());
+        }
+
+        let _permit = self.permits.acquire().await.expect("semaphore closed");
+        let mut attempt = 0;
+
+        loop {
+            self.acquire_token().await;
+
+            // Ok(body) ends the loop, Err((error, retry after)) tries again
+            let result = match self.http.get(url.clone()).send().await {
+                Ok(resp) => {
+                    let status = resp.status();
+                    let retry_after = parse_retry_after(resp.headers().get(RETRY_AFTER));
+
+                    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+                        Err((ClientError::Status(status), retry_after))
+                    } else if !status.is_success() {
+                        return Err(ClientError::Status(status));
+                    } else {
+                        match resp.text().await {
+                            // a lagged response is a 200 with a maxlag error in the body
+                            Ok(body) if is_maxlag_error(&body) => Err((ClientError::Lagged, retry_after)),
+                            Ok(body) => Ok(body),
+                            Err(e) => Err((ClientError::Request(e), None)),
+                        }
+                    }
+                }
+                Err(e) => Err((ClientError::Request(e), None)),
+            };
+
+            let (error, retry_after) = match result {
+                Ok(body) => return Ok(body),
+                Err(e) => e,
+            };
+
+            attempt += 1;
+            if attempt > self.config.max_retries {
+                return Err(error);
+            }
+
+            // the server's Retry-After wins over our own backoff, and holds
+            // back every worker since they all hit the same server
+            let delay = match retry_after {
+                Some(delay) => {
+                    self.bucket.lock().await.pause(delay);
+                    delay
+                }
+                None => self.backoff(attempt),
+            };
+
+            eprintln!("{}, retrying in {:?} (attempt {})", error, delay, attempt);
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn acquire_token(&self) {
+        loop {
+            let wait = { self.bucket.lock().await.take() };
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+
+    /// Exponential backoff with full jitter: a random delay between zero and
+    /// `base_backoff * 2^attempt`, capped at `max_backoff`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let max = self.config.base_backoff
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.config.max_backoff);
+
+        max.mul_f64(fastrand::f64())
+    }
+}
+
+/// `Retry-After` is either a number of seconds or an HTTP date. MediaWiki only
+/// sends seconds, so dates are ignored and we fall back to our own backoff.
+fn parse_retry_after(value: Option<&reqwest::header::HeaderValue>) -> Option<Duration> {
+    let secs = value?.to_str().ok()?.trim().parse::<u64>().ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+fn is_maxlag_error(body: &str) -> bool {
+    // cheap check before parsing, almost every response is not an error
+    if !body.contains("maxlag") { return false; }
+
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v.get("error")?.get("code")?.as_str().map(|c| c == "maxlag"))
+        .unwrap_or(false)
+}
@@ -0,0 +1,104 @@
+mod report;
+
+use report::Report;
+
+use scraper::snapshot;
+use std::io::Write;
+
+/// Compare two snapshots of a wiki's graph: added and removed articles and
+/// links, renamed articles and the articles whose degree changed the most.
+///
+/// `diff OLD NEW [--top N] [--json PATH]`
+///
+/// OLD and NEW are snapshot files written by the `snapshot` binary, or
+/// `db:WIKI` for the current graph of a wiki in the database (see
+/// [`snapshot::load`]). A summary is printed, and the full report is written
+/// as JSON to PATH (`-` for stdout, the summary then goes to stderr).
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut sources = vec![];
+    let mut top = 20;
+    let mut json = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--top" => top = args.next().and_then(|n| n.parse().ok()).expect("--top takes a number"),
+            "--json" => json = Some(args.next().expect("--json takes a path")),
+            _ => sources.push(arg),
+        }
+    }
+
+    let [old, new] = sources.as_slice() else {
+        eprintln!("usage: diff OLD NEW [--top N] [--json PATH]");
+        std::process::exit(2);
+    };
+
+    let old = snapshot::load(old).await.expect("Failed to load old graph");
+    let new = snapshot::load(new).await.expect("Failed to load new graph");
+    let report = report::diff(&old, &new, top);
+
+    match json.as_deref() {
+        Some("-") => {
+            print_summary(&mut std::io::stderr(), &report, top).unwrap();
+            serde_json::to_writer(std::io::stdout().lock(), &report).expect("Failed to write report");
+            println!();
+        }
+        Some(path) => {
+            print_summary(&mut std::io::stdout(), &report, top).unwrap();
+            let file = std::fs::File::create(path).expect("Failed to create report");
+            serde_json::to_writer(std::io::BufWriter::new(file), &report).expect("Failed to write report");
+        }
+        None => print_summary(&mut std::io::stdout(), &report, top).unwrap(),
+    }
+}
+
+fn print_summary(out: &mut impl Write, report: &Report, top: usize) -> std::io::Result<()> {
+    let name = |s: &report::Summary| format!(
+        "{} {}",
+        s.wiki.as_deref().unwrap_or("?"),
+        s.taken_at.as_deref().unwrap_or("(now)"),
+    );
+
+    writeln!(out, "{} -> {}", name(&report.old), name(&report.new))?;
+    writeln!(
+        out,
+        "articles: {} -> {} (+{} added, -{} removed, {} renamed)",
+        report.old.articles,
+        report.new.articles,
+        report.added_articles.len(),
+        report.removed_articles.len(),
+        report.renamed.len(),
+    )?;
+    writeln!(
+        out,
+        "links:    {} -> {} (+{} added, -{} removed)",
+        report.old.edges,
+        report.new.edges,
+        report.added_edges.len(),
+        report.removed_edges.len(),
+    )?;
+
+    if !report.renamed.is_empty() {
+        writeln!(out, "\nrenamed:")?;
+        for rename in report.renamed.iter().take(top) {
+            writeln!(out, "  {} -> {}", rename.from, rename.to)?;
+        }
+        if report.renamed.len() > top {
+            writeln!(out, "  ... and {} more", report.renamed.len() - top)?;
+        }
+    }
+
+    if !report.degree_changes.is_empty() {
+        writeln!(out, "\nbiggest degree changes:")?;
+        for change in &report.degree_changes {
+            writeln!(
+                out,
+                "  {}: in {} -> {}, out {} -> {}",
+                change.title, change.in_before, change.in_after, change.out_before, change.out_after,
+            )?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,265 @@
+use scraper::snapshot::Snapshot;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Everything that changed between two snapshots of a wiki's graph.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub old: Summary,
+    pub new: Summary,
+    pub added_articles: Vec<String>,
+    pub removed_articles: Vec<String>,
+    pub renamed: Vec<Rename>,
+    pub added_edges: Vec<Edge>,
+    pub removed_edges: Vec<Edge>,
+    /// articles in both snapshots, biggest change of in- plus out-degree first
+    pub degree_changes: Vec<DegreeChange>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub wiki: Option<String>,
+    pub taken_at: Option<String>,
+    pub articles: usize,
+    pub edges: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Rename {
+    pub id: i32,
+    pub from: String,
+    pub to: String,
+}
+
+/// A link between two articles, by their title in the newer snapshot
+/// (the older one for removed articles).
+#[derive(Debug, Serialize)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DegreeChange {
+    pub title: String,
+    pub in_before: usize,
+    pub in_after: usize,
+    pub out_before: usize,
+    pub out_after: usize,
+}
+
+impl DegreeChange {
+    pub fn delta(&self) -> usize {
+        self.in_before.abs_diff(self.in_after) + self.out_before.abs_diff(self.out_after)
+    }
+}
+
+/// Compare two snapshots, keeping the `top` biggest degree changes.
+///
+/// Articles are matched by title. An article only in the old snapshot whose id
+/// belongs to an article only in the new one was renamed: moves keep the id
+/// (see the `changes` binary), so the new title is the same article.
+pub fn diff(old: &Snapshot, new: &Snapshot, top: usize) -> Report {
+    // every article gets a key: new articles are 0..new.len(), old articles
+    // share the key of their match and removed ones come after the new ones
+    let new_by_title = new.articles.iter().enumerate().map(|(i, a)| (a.title.as_str(), i)).collect::<HashMap<_, _>>();
+    let new_by_id = new.articles.iter().enumerate().map(|(i, a)| (a.id, i)).collect::<HashMap<_, _>>();
+    let old_by_title = old.articles.iter().map(|a| a.title.as_str()).collect::<HashSet<_>>();
+
+    let mut matched = vec![false; new.articles.len()];
+    let mut old_keys = Vec::with_capacity(old.articles.len());
+    let mut renamed = vec![];
+    let mut removed_articles = vec![];
+
+    for (i, article) in old.articles.iter().enumerate() {
+        let key = match new_by_title.get(article.title.as_str()) {
+            Some(&key) => key,
+            None => match new_by_id.get(&article.id) {
+                // the id's new title must not have existed before, or it is a different article
+                Some(&key) if !old_by_title.contains(new.articles[key].title.as_str()) => {
+                    renamed.push(Rename { id: article.id, from: article.title.clone(), to: new.articles[key].title.clone() });
+                    key
+                }
+                _ => {
+                    removed_articles.push(article.title.clone());
+                    new.articles.len() + i
+                }
+            },
+        };
+
+        if key < matched.len() { matched[key] = true; }
+        old_keys.push(key);
+    }
+
+    let added_articles = new.articles.iter()
+        .zip(&matched)
+        .filter(|(_, &m)| !m)
+        .map(|(a, _)| a.title.clone())
+        .collect::<Vec<_>>();
+
+    let keys = new.articles.len() + old.articles.len();
+    let title = |key: usize| match key.checked_sub(new.articles.len()) {
+        None => new.articles[key].title.clone(),
+        Some(i) => old.articles[i].title.clone(),
+    };
+
+    // links of both snapshots by key, sorted so they can be merged
+    let old_id_keys = old.articles.iter().zip(&old_keys).map(|(a, &k)| (a.id, k)).collect::<HashMap<_, _>>();
+    let mut old_links = vec![vec![]; keys];
+    for (article, &key) in old.articles.iter().zip(&old_keys) {
+        old_links[key] = sorted_keys(&article.links, &old_id_keys);
+    }
+
+    let mut new_links = vec![vec![]; keys];
+    for (key, article) in new.articles.iter().enumerate() {
+        new_links[key] = sorted_keys(&article.links, &new_by_id);
+    }
+
+    let mut added_edges = vec![];
+    let mut removed_edges = vec![];
+    let mut in_before = vec![0; keys];
+    let mut in_after = vec![0; keys];
+
+    for key in 0..keys {
+        for &to in &old_links[key] { in_before[to] += 1; }
+        for &to in &new_links[key] { in_after[to] += 1; }
+
+        let (added, removed) = merge(&old_links[key], &new_links[key]);
+        added_edges.extend(added.into_iter().map(|to| Edge { from: title(key), to: title(to) }));
+        removed_edges.extend(removed.into_iter().map(|to| Edge { from: title(key), to: title(to) }));
+    }
+
+    let mut degree_changes = (0..new.articles.len())
+        .filter(|&key| matched[key])
+        .map(|key| DegreeChange {
+            title: title(key),
+            in_before: in_before[key],
+            in_after: in_after[key],
+            out_before: old_links[key].len(),
+            out_after: new_links[key].len(),
+        })
+        .filter(|c| c.delta() > 0)
+        .collect::<Vec<_>>();
+
+    degree_changes.sort_by(|a, b| b.delta().cmp(&a.delta()).then_with(|| a.title.cmp(&b.title)));
+    degree_changes.truncate(top);
+
+    Report {
+        old: Summary {
+            wiki: old.wiki.clone(),
+            taken_at: old.taken_at.clone(),
+            articles: old.articles.len(),
+            edges: old_links.iter().map(Vec::len).sum(),
+        },
+        new: Summary {
+            wiki: new.wiki.clone(),
+            taken_at: new.taken_at.clone(),
+            articles: new.articles.len(),
+            edges: new_links.iter().map(Vec::len).sum(),
+        },
+        added_articles,
+        removed_articles,
+        renamed,
+        added_edges,
+        removed_edges,
+        degree_changes,
+    }
+}
+
+/// Map link ids to keys, dropping links to articles outside the snapshot.
+fn sorted_keys(links: &[i32], keys: &HashMap<i32, usize>) -> Vec<usize> {
+    let mut out = links.iter().filter_map(|id| keys.get(id).copied()).collect::<Vec<_>>();
+    out.sort_unstable();
+    out.dedup();
+    out
+}
+
+/// (only in `new`, only in `old`) of two sorted lists.
+fn merge(old: &[usize], new: &[usize]) -> (Vec<usize>, Vec<usize>) {
+    let (mut added, mut removed) = (vec![], vec![]);
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        match (old.get(i), new.get(j)) {
+            (Some(a), Some(b)) if a == b => { i += 1; j += 1; }
+            (Some(a), Some(b)) if a < b => { removed.push(*a); i += 1; }
+            (Some(_), Some(b)) => { added.push(*b); j += 1; }
+            (Some(a), None) => { removed.push(*a); i += 1; }
+            (None, Some(b)) => { added.push(*b); j += 1; }
+            (None, None) => unreachable!(),
+        }
+    }
+
+    (added, removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scraper::snapshot::Article;
+
+    fn snapshot(articles: &[(i32, &str, &[i32])]) -> Snapshot {
+        Snapshot {
+            wiki: Some("testwiki".to_string()),
+            taken_at: None,
+            articles: articles
+                .iter()
+                .map(|&(id, title, links)| Article { id, title: title.to_string(), links: links.to_vec() })
+                .collect(),
+        }
+    }
+
+    fn edges(edges: &[Edge]) -> Vec<(&str, &str)> {
+        edges.iter().map(|e| (e.from.as_str(), e.to.as_str())).collect()
+    }
+
+    #[test]
+    fn finds_renamed_added_and_removed_articles_and_links() {
+        let old = snapshot(&[(1, "Alpha", &[2]), (2, "Beta", &[3]), (3, "Gamma", &[]), (4, "Delta", &[1])]);
+        let new = snapshot(&[(1, "Alpha", &[2, 3]), (2, "Beta (letter)", &[3]), (3, "Gamma", &[]), (5, "Epsilon", &[1])]);
+
+        let report = diff(&old, &new, 10);
+
+        assert_eq!(report.renamed.len(), 1);
+        assert_eq!((report.renamed[0].id, report.renamed[0].from.as_str(), report.renamed[0].to.as_str()), (2, "Beta", "Beta (letter)"));
+        assert_eq!(report.added_articles, vec!["Epsilon"]);
+        assert_eq!(report.removed_articles, vec!["Delta"]);
+
+        // the renamed article keeps its links, so they are neither added nor removed
+        assert_eq!(edges(&report.added_edges), vec![("Alpha", "Gamma"), ("Epsilon", "Alpha")]);
+        assert_eq!(edges(&report.removed_edges), vec![("Delta", "Alpha")]);
+        assert_eq!((report.old.edges, report.new.edges), (3, 4));
+
+        let changes = report.degree_changes.iter().map(|c| (c.title.as_str(), c.delta())).collect::<Vec<_>>();
+        assert_eq!(changes, vec![("Alpha", 1), ("Gamma", 1)]);
+        assert_eq!(diff(&old, &new, 1).degree_changes.len(), 1);
+    }
+
+    #[test]
+    fn an_id_taking_an_existing_title_is_not_a_rename() {
+        // Alpha's id now has the title Beta, which id 2 had: the diff goes by
+        // title, so Alpha is gone and Beta is the same article with a new id
+        let old = snapshot(&[(1, "Alpha", &[]), (2, "Beta", &[1])]);
+        let new = snapshot(&[(1, "Beta", &[])]);
+
+        let report = diff(&old, &new, 10);
+
+        assert!(report.renamed.is_empty());
+        assert!(report.added_articles.is_empty());
+        assert_eq!(report.removed_articles, vec!["Alpha"]);
+        assert!(report.added_edges.is_empty());
+        assert_eq!(edges(&report.removed_edges), vec![("Beta", "Alpha")]);
+    }
+
+    #[test]
+    fn ignores_links_out_of_the_snapshot_and_duplicates() {
+        let old = snapshot(&[(1, "Alpha", &[2, 2, 99])]);
+        let new = snapshot(&[(1, "Alpha", &[2]), (2, "Beta", &[])]);
+
+        let report = diff(&old, &new, 10);
+
+        assert_eq!(report.old.edges, 0);
+        assert_eq!(edges(&report.added_edges), vec![("Alpha", "Beta")]);
+        assert_eq!(report.added_articles, vec!["Beta"]);
+    }
+}
@@ -0,0 +1,132 @@
+use arrow_array::{ArrayRef, Int32Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use scraper::snapshot::{escape, Snapshot};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Rows per Parquet row group, so the whole edge list never has to be in one batch.
+const BATCH_ROWS: usize = 1 << 20;
+
+/// Every link between two articles of the snapshot, as (from, to) indexes into
+/// `articles`. Links to articles outside the snapshot are dropped.
+fn edges(snapshot: &Snapshot) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let by_id = snapshot.articles.iter().enumerate().map(|(i, a)| (a.id, i)).collect::<HashMap<_, _>>();
+
+    snapshot.articles.iter().enumerate().flat_map(move |(i, a)| {
+        let to = a.links.iter().filter_map(|l| by_id.get(l).copied()).collect::<Vec<_>>();
+        to.into_iter().map(move |j| (i, j))
+    })
+}
+
+/// One link per line: the source and target title, separated by a tab.
+/// Titles use the escapes of snapshot files.
+pub fn tsv(snapshot: &Snapshot, out: &mut impl Write) -> io::Result<()> {
+    let titles = snapshot.articles.iter().map(|a| escape(&a.title)).collect::<Vec<_>>();
+
+    for (from, to) in edges(snapshot) {
+        writeln!(out, "{}\t{}", titles[from], titles[to])?;
+    }
+
+    Ok(())
+}
+
+/// GraphML with article ids as node ids and the title as a `title` attribute.
+pub fn graphml(snapshot: &Snapshot, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+    writeln!(out, r#"  <key id="title" for="node" attr.name="title" attr.type="string"/>"#)?;
+    writeln!(out, r#"  <graph id="{}" edgedefault="directed">"#, xml(snapshot.wiki.as_deref().unwrap_or("wikilinks")))?;
+
+    for article in &snapshot.articles {
+        writeln!(out, r#"    <node id="{}"><data key="title">{}</data></node>"#, article.id, xml(&article.title))?;
+    }
+
+    for (from, to) in edges(snapshot) {
+        writeln!(out, r#"    <edge source="{}" target="{}"/>"#, snapshot.articles[from].id, snapshot.articles[to].id)?;
+    }
+
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</graphml>")
+}
+
+/// GEXF 1.3 with article ids as node ids and titles as labels.
+pub fn gexf(snapshot: &Snapshot, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#)?;
+    writeln!(out, "  <meta>")?;
+    writeln!(out, "    <creator>wikilinks</creator>")?;
+    if let Some(wiki) = &snapshot.wiki {
+        writeln!(out, "    <description>{}</description>", xml(wiki))?;
+    }
+    writeln!(out, "  </meta>")?;
+    writeln!(out, r#"  <graph mode="static" defaultedgetype="directed">"#)?;
+
+    writeln!(out, "    <nodes>")?;
+    for article in &snapshot.articles {
+        writeln!(out, r#"      <node id="{}" label="{}"/>"#, article.id, xml(&article.title))?;
+    }
+    writeln!(out, "    </nodes>")?;
+
+    writeln!(out, "    <edges>")?;
+    for (n, (from, to)) in edges(snapshot).enumerate() {
+        writeln!(out, r#"      <edge id="{}" source="{}" target="{}"/>"#, n, snapshot.articles[from].id, snapshot.articles[to].id)?;
+    }
+    writeln!(out, "    </edges>")?;
+
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</gexf>")
+}
+
+/// `nodes.parquet` (id, title) and `edges.parquet` (source, target ids) in `dir`.
+pub fn parquet(snapshot: &Snapshot, dir: &Path) -> Result<(), Box<dyn Error + Send + Sync>> {
+    std::fs::create_dir_all(dir)?;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("title", DataType::Utf8, false),
+    ]));
+    let mut writer = ArrowWriter::try_new(File::create(dir.join("nodes.parquet"))?, Arc::clone(&schema), None)?;
+
+    for chunk in snapshot.articles.chunks(BATCH_ROWS) {
+        let ids = Int32Array::from_iter_values(chunk.iter().map(|a| a.id));
+        let titles = StringArray::from_iter_values(chunk.iter().map(|a| a.title.as_str()));
+        let columns: Vec<ArrayRef> = vec![Arc::new(ids), Arc::new(titles)];
+        writer.write(&RecordBatch::try_new(Arc::clone(&schema), columns)?)?;
+    }
+    writer.close()?;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("source", DataType::Int32, false),
+        Field::new("target", DataType::Int32, false),
+    ]));
+    let mut writer = ArrowWriter::try_new(File::create(dir.join("edges.parquet"))?, Arc::clone(&schema), None)?;
+
+    let mut edges = edges(snapshot).peekable();
+    while edges.peek().is_some() {
+        let (sources, targets): (Vec<_>, Vec<_>) = edges
+            .by_ref()
+            .take(BATCH_ROWS)
+            .map(|(from, to)| (snapshot.articles[from].id, snapshot.articles[to].id))
+            .unzip();
+
+        let columns: Vec<ArrayRef> = vec![Arc::new(Int32Array::from(sources)), Arc::new(Int32Array::from(targets))];
+        writer.write(&RecordBatch::try_new(Arc::clone(&schema), columns)?)?;
+    }
+    writer.close()?;
+
+    Ok(())
+}
+
+/// Escape text for XML attributes and elements.
+fn xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
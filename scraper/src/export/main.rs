@@ -0,0 +1,76 @@
+mod formats;
+
+use scraper::snapshot;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Export a graph for other tools.
+///
+/// `export SOURCE FORMAT [--around TITLE] [--hops K] [-o PATH]`
+///
+/// SOURCE is anything [`snapshot::load`] reads: a snapshot file, `db:WIKI` or
+/// `postgres://...#WIKI`. FORMAT is one of
+/// - `tsv`      edge list of titles, one link per line
+/// - `graphml`  GraphML, for NetworkX and Gephi
+/// - `gexf`     GEXF, for Gephi
+/// - `parquet`  `nodes.parquet` and `edges.parquet`, for DuckDB and pandas
+///
+/// `--around TITLE` only exports the articles at most `--hops` (default 1)
+/// links away from TITLE in either direction. The text formats are written to
+/// PATH or stdout, Parquet files to the directory PATH (default `.`).
+#[tokio::main]
+async fn main() {
+    let mut args = std::env::args().skip(1);
+    let mut positional = vec![];
+    let mut around = None;
+    let mut hops = 1;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--around" => around = Some(args.next().expect("--around takes a title")),
+            "--hops" => hops = args.next().and_then(|n| n.parse().ok()).expect("--hops takes a number"),
+            "-o" | "--output" => output = Some(args.next().expect("-o takes a path")),
+            _ => positional.push(arg),
+        }
+    }
+
+    let [source, format] = positional.as_slice() else {
+        eprintln!("usage: export SOURCE tsv|graphml|gexf|parquet [--around TITLE] [--hops K] [-o PATH]");
+        std::process::exit(2);
+    };
+
+    let mut graph = snapshot::load(source).await.expect("Failed to load graph");
+
+    if let Some(title) = &around {
+        graph = graph.neighbourhood(title, hops).unwrap_or_else(|| {
+            eprintln!("{} is not in the graph", title);
+            std::process::exit(1);
+        });
+    }
+
+    eprintln!("Exporting {} articles", graph.articles.len());
+
+    if format == "parquet" {
+        let dir = output.as_deref().unwrap_or(".");
+        formats::parquet(&graph, Path::new(dir)).expect("Failed to write Parquet");
+        return;
+    }
+
+    let mut out: Box<dyn Write> = match &output {
+        Some(path) => Box::new(BufWriter::new(std::fs::File::create(path).expect("Failed to create output"))),
+        None => Box::new(BufWriter::new(std::io::stdout().lock())),
+    };
+
+    let result = match format.as_str() {
+        "tsv" => formats::tsv(&graph, &mut out),
+        "graphml" => formats::graphml(&graph, &mut out),
+        "gexf" => formats::gexf(&graph, &mut out),
+        _ => {
+            eprintln!("unknown format {}, expected tsv, graphml, gexf or parquet", format);
+            std::process::exit(2);
+        }
+    };
+
+    result.and_then(|_| out.flush()).expect("Failed to write export");
+}
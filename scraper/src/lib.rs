@@ -0,0 +1,5 @@
+pub mod categories;
+pub mod client;
+pub mod pagelinks;
+pub mod snapshot;
+pub mod wiki;
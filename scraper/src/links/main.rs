@@ -1,13 +1,12 @@
-mod dto;
+mod state;
 
-use dto::*;
+use state::{Batch, Outcome};
 
-use reqwest::Url;
+use scraper::client::{Client, ClientConfig};
+use scraper::pagelinks::{fetch_links, FetchedLinks};
+use scraper::wiki::WikiConfig;
 use sqlx::postgres::PgPoolOptions;
-use sqlx::query;
-use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
 use tokio::sync::Mutex;
 
 #[tokio::main]
@@ -18,29 +17,19 @@ async fn main() {
         .await
         .expect("Failed to connect to database");
 
-    let mut queue = Vec::<String>::new();
+    let wiki = Arc::new(WikiConfig::from_env());
+    println!("Crawling links of {} from {}", wiki.id, wiki.api_url);
 
-    // select articles that do not have links and add them to the queue
-    let articles = query!("SELECT title FROM articles WHERE cardinality(links) = 0 ORDER BY title desc")
-        .fetch_all(&pool)
+    // select articles that are still pending or whose last attempt failed and
+    // is due for a retry, already split into 10 article batches. Finished
+    // articles are never fetched again, even if they really have no links.
+    let queue = state::load_batches(&pool, &wiki.id, 10)
         .await
-        .expect("Failed to load articles!")
-        .into_iter()
-        .map(|r| r.title);
-    queue.extend(articles);
-
-    // split the queue into 10 article chunks and clone the chunks (which are slices of Strings)
-    // to a vector of chunks (now a vector of Strings)
-    let queue = queue
-        .chunks(10)
-        .map(|c| c
-            .iter()
-            .map(|d| d.clone())
-            .collect::<Vec<_>>())
-        .collect::<Vec<_>>();
+        .expect("Failed to load articles!");
+    println!("Loaded {} batches", queue.len());
     let queue = Arc::new(Mutex::new(queue));
 
-    let (store_tx, mut store_rx) = tokio::sync::mpsc::unbounded_channel::<(String, Vec<String>)>();
+    let (store_tx, mut store_rx) = tokio::sync::mpsc::unbounded_channel::<Outcome>();
 
     // In another thread, listen for messages from the other threads
     // to store the results of a call to the Wikipedia API. There's
@@ -49,137 +38,144 @@ async fn main() {
     // between threads. A previous iteration used SQLite, which can
     // only be access from one thread at a time and would require
     // locking and unlock a Mutex for every write.
-    tokio::spawn(async move {
-        while let Some((name, links)) = store_rx.recv().await {
-            println!("Updating {} = {}", name, links.len());
-
-            query!("UPDATE articles SET links = $2 WHERE title = $1", name, links.as_slice())
-                .execute(&pool)
-                .await
-                .expect("Failed to insert article");
+    let writer_wiki = Arc::clone(&wiki);
+    let writer = tokio::spawn(async move {
+        let wiki = &writer_wiki.id;
+
+        while let Some(outcome) = store_rx.recv().await {
+            match outcome {
+                Outcome::Done { title, links } => {
+                    println!("Updating {} = {}", title, links.len());
+
+                    state::mark_done(&pool, wiki, &title, &links)
+                        .await
+                        .expect("Failed to insert article");
+                }
+                Outcome::Failed { titles, error } => {
+                    eprintln!("Failed batch @ {}: {}", titles[0], error);
+
+                    state::mark_failed(&pool, wiki, &titles, &error)
+                        .await
+                        .expect("Failed to record failed batch");
+                }
+            }
         }
     });
 
     let mut threads = vec![];
 
-    // spawn 5 threads to request links concurrently. significantly speeds up
-    // the link fetching process, which can still take a long time.
-    // Note: requests will begin to fail if too many threads are spawned (10+)
-    for _ in 0..5 {
+    // every worker shares one client, so the rate limit and backoff apply to
+    // all of them together. Spawn as many workers as requests may be in flight.
+    // Note: requests will begin to fail if too many are in flight (10+)
+    let client = Arc::new(Client::new(ClientConfig::from_env()));
+
+    for _ in 0..client.config().concurrency {
         let store_tx = store_tx.clone();
         let queue = Arc::clone(&queue);
+        let client = Arc::clone(&client);
+        let wiki = Arc::clone(&wiki);
 
         let thread = tokio::spawn(async move {
-            // Each thread needs to use a reqwest Client to cache DNS requests or
-            // requests will fail due to DNS server overload, especially if the server
-            // is running on the user's network gateway
-            let client = reqwest::Client::new();
-
             loop {
                 let current = { queue.lock().await.pop() };
-                if current.is_none() { break; }
-                let current = current.unwrap();
-
-                let mut r#continue = PageLinksContinue { pl_continue: None, r#continue: None };
-                let mut collected = HashMap::new();
+                let Some(Batch { mut titles, mut attempts }) = current else { break };
 
+                // a failed batch is retried with backoff until it succeeds or runs
+                // out of attempts, with only the titles that didn't come back.
+                // Progress is recorded in crawl_state either way, so a restart
+                // continues with the same attempt count.
                 loop {
-                    let mut url = Url::parse("https://en.wikipedia.org/w/api.php?action=query&format=json&prop=links&formatversion=2&pllimit=max").unwrap();
-                    url.query_pairs_mut().append_pair("titles", current.join("|").as_str());
-
-                    let PageLinksContinue { r#continue: cont, pl_continue } = r#continue;
-
-                    // continue parameters to get the next page of results
-
-                    if let Some(ref pl_continue) = pl_continue {
-                        url.query_pairs_mut().append_pair("plcontinue", pl_continue.as_str());
-                    }
-
-                    if let Some(ref cont) = cont {
-                        url.query_pairs_mut().append_pair("continue", cont.as_str());
-                    }
-
-                    print!("Sending request cont={:?} @ {}...", pl_continue, current[0]);
-
-                    let resp = client.get(url)
-                        .header("user-agent", "qwright10 quentin.wright@ufl.edu / research @ University of Florida")
-                        .send()
-                        .await
-                        .unwrap()
-                        .text()
-                        .await
-                        .unwrap();
-
-                    // if the request fails, we can exit and start again.
-                    // progress continues from the last request
-                    let resp: PageLinks = match serde_json::from_str(&resp) {
-                        Ok(r) => r,
-                        Err(_) => {
-                            println!("========{}========", resp);
-                            panic!();
-                        }
-                    };
-
-                    // sleep to avoid request failure and spamming API
-                    tokio::time::sleep(Duration::from_millis(50)).await;
-
-                    // some pages will have no links
-                    let links = match resp.query {
-                        Some(links) => links,
-                        None => {
-                            break;
+                    let (failed, error) = match fetch_links(&client, &wiki, &titles).await {
+                        Ok(fetched) => {
+                            let (done, absent) = sort_out(&titles, fetched);
+                            for (title, links) in done {
+                                println!("{} contains {} links", title, links.len());
+
+                                // send the results to the db writer thread above
+                                store_tx.send(Outcome::Done { title, links }).unwrap();
+                            }
+
+                            if absent.is_empty() { break; }
+                            let error = format!("{} missing from response", absent[0]);
+                            (absent, error)
                         }
+                        Err(error) => (titles, error),
                     };
 
-                    let links = links
-                        .get("pages")
-                        .unwrap();
+                    attempts += 1;
+                    store_tx.send(Outcome::Failed { titles: failed.clone(), error }).unwrap();
+                    titles = failed;
 
-                    for page in links {
-                        let title = page.title.clone();
-                        let l = page.links.as_ref()
-                            .unwrap_or(&Vec::new())
-                            .iter()
-                            .map(|l| l.title.clone())
-                            .collect::<Vec<_>>();
-
-                        // println!("Collecting {} = {:?}", title, l);
-
-                        let mut existing = collected.get_mut(&title);
-                        if let None = existing {
-                            collected.insert(title.clone(), Vec::new());
-                            existing = Some(collected.get_mut(&title).unwrap());
-                        }
-
-                        existing.unwrap().extend(l);
-                    }
-
-                    // if there are more pages, continue requesting
-                    if let Some(cont) = resp.r#continue {
-                        r#continue = cont;
-                    } else {
-                        // otherwise break and continue with the next article in the queue
-                        break;
-                    }
-                }
-
-                for (title, links) in collected {
-                    println!("{} contains {} links", title, links.len());
-
-                    // send the results to the db writer thread above
-                    store_tx.send((title, links)).unwrap();
+                    if attempts >= state::MAX_ATTEMPTS { break; }
+                    tokio::time::sleep(state::backoff(attempts)).await;
                 }
             }
         });
 
         threads.push(thread);
-
-        // spawn threads 1s apart for no particular reason
-        tokio::time::sleep(Duration::from_millis(1000)).await;
     }
 
     // wait for all tasks to terminate
     for thread in threads {
         thread.await.unwrap();
     }
-}
\ No newline at end of file
+
+    // close the channel so the writer can finish the remaining updates
+    drop(store_tx);
+    writer.await.unwrap();
+}
+
+/// Split the links fetched for a batch into finished titles with their links
+/// and titles to retry. Titles the wiki reports as missing are finished
+/// without links, so a deleted page doesn't hold up the rest of its batch;
+/// titles left out of the response altogether are retried.
+fn sort_out(titles: &[String], mut fetched: FetchedLinks) -> (Vec<(String, Vec<String>)>, Vec<String>) {
+    let mut done = vec![];
+    let mut absent = vec![];
+
+    for title in titles {
+        match fetched.links.remove(title) {
+            Some(links) => done.push((title.clone(), links)),
+            None if fetched.missing.contains(title) => done.push((title.clone(), vec![])),
+            None => absent.push(title.clone()),
+        }
+    }
+
+    (done, absent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(titles: &[&str]) -> Vec<String> {
+        titles.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn finishes_a_batch_with_a_missing_title() {
+        let fetched = FetchedLinks {
+            links: [("A".to_string(), titles(&["B"])), ("C".to_string(), vec![])].into(),
+            missing: ["Deleted".to_string()].into(),
+        };
+
+        let (done, absent) = sort_out(&titles(&["A", "Deleted", "C"]), fetched);
+
+        assert_eq!(done, vec![
+            ("A".to_string(), titles(&["B"])),
+            ("Deleted".to_string(), vec![]),
+            ("C".to_string(), vec![]),
+        ]);
+        assert!(absent.is_empty());
+    }
+
+    #[test]
+    fn retries_only_titles_left_out_of_the_response() {
+        let fetched = FetchedLinks { links: [("A".to_string(), titles(&["B"]))].into(), ..FetchedLinks::default() };
+
+        let (done, absent) = sort_out(&titles(&["A", "Lost"]), fetched);
+
+        assert_eq!(done, vec![("A".to_string(), titles(&["B"]))]);
+        assert_eq!(absent, titles(&["Lost"]));
+    }
+}
@@ -0,0 +1,94 @@
+use sqlx::{query, PgPool};
+use std::time::Duration;
+
+/// Number of attempts before a title is left in the `failed` state for good.
+/// Reset `attempts` in `crawl_state` to retry it on the next run.
+pub const MAX_ATTEMPTS: i32 = 5;
+
+/// A group of titles requested from the API together.
+#[derive(Debug, Clone)]
+pub struct Batch {
+    pub titles: Vec<String>,
+    pub attempts: i32,
+}
+
+/// The result of crawling a title, sent to the db writer task.
+#[derive(Debug)]
+pub enum Outcome {
+    Done { title: String, links: Vec<String> },
+    Failed { titles: Vec<String>, error: String },
+}
+
+/// Delay before retrying a batch that has failed `attempts` times.
+/// Doubles with every attempt, starting at 5s and capped at 10 minutes.
+pub fn backoff(attempts: i32) -> Duration {
+    let secs = 5u64.saturating_mul(1 << attempts.clamp(0, 16));
+    Duration::from_secs(secs.min(600))
+}
+
+/// Load every title that is pending or whose failed attempt is due for a retry,
+/// split into batches of `size` titles.
+pub async fn load_batches(pool: &PgPool, wiki: &str, size: usize) -> Result<Vec<Batch>, sqlx::Error> {
+    let rows = query!(
+        "SELECT title, attempts FROM crawl_state
+         WHERE wiki = $1 AND (status = 'pending' OR (status = 'failed' AND attempts < $2 AND next_attempt_at <= now()))
+         ORDER BY title DESC",
+        wiki,
+        MAX_ATTEMPTS
+    )
+        .fetch_all(pool)
+        .await?;
+
+    // a batch is retried as many times as its most-failed title allows
+    let batches = rows
+        .chunks(size)
+        .map(|c| Batch {
+            titles: c.iter().map(|r| r.title.clone()).collect(),
+            attempts: c.iter().map(|r| r.attempts).max().unwrap_or(0),
+        })
+        .collect();
+
+    Ok(batches)
+}
+
+/// Store the links of a title and mark it as done in the same transaction,
+/// so a restart never sees links without the finished state or vice versa.
+pub async fn mark_done(pool: &PgPool, wiki: &str, title: &str, links: &[String]) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    query!("UPDATE articles SET links = $3 WHERE wiki = $1 AND title = $2", wiki, title, links)
+        .execute(&mut *tx)
+        .await?;
+
+    query!(
+        "INSERT INTO crawl_state (wiki, title, status, last_error, updated_at) VALUES ($1, $2, 'done', NULL, now())
+         ON CONFLICT (wiki, title) DO UPDATE SET status = 'done', last_error = NULL, updated_at = now()",
+        wiki,
+        title
+    )
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
+/// Record a failed attempt for every title in a batch and schedule the next one.
+/// The delay is the same as [`backoff`], computed by Postgres from the new attempt count.
+pub async fn mark_failed(pool: &PgPool, wiki: &str, titles: &[String], error: &str) -> Result<(), sqlx::Error> {
+    query!(
+        "UPDATE crawl_state
+         SET status = 'failed',
+             attempts = attempts + 1,
+             last_error = $3,
+             next_attempt_at = now() + make_interval(secs => least(5 * power(2, least(attempts + 1, 16)), 600)),
+             updated_at = now()
+         WHERE wiki = $1 AND title = any ($2) AND status <> 'done'",
+        wiki,
+        titles,
+        error
+    )
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use std::collections::{HashMap, HashSet};
+use serde::Deserialize;
+use crate::client::Client;
+use crate::wiki::WikiConfig;
+
+#[derive(Deserialize, Debug)]
+pub struct PageLinksContinue {
+    #[serde(rename = "plcontinue")]
+    pub pl_continue: Option<String>,
+
+    #[serde(rename = "continue")]
+    pub r#continue: Option<String>
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PageLinksPage {
+    pub title: String,
+    pub links: Option<Vec<PageLink>>,
+
+    /// set for titles that do not exist (anymore) on the wiki
+    #[serde(default)]
+    pub missing: bool
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PageLink {
+    pub title: String
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PageLinks {
+    #[serde(rename = "continue")]
+    pub r#continue: Option<PageLinksContinue>,
+    pub query: Option<HashMap<String, Vec<PageLinksPage>>>
+}
+
+/// The links of a group of titles, as returned by [`fetch_links`].
+#[derive(Debug, Default)]
+pub struct FetchedLinks {
+    /// Links of every title that exists, empty for those without links.
+    pub links: HashMap<String, Vec<String>>,
+    /// Titles the wiki reported as not existing (anymore).
+    pub missing: HashSet<String>,
+}
+
+/// Request the links of every title in `titles` (at most 50), following continue
+/// parameters until the API has returned all of them. Titles that do not exist
+/// on the wiki are listed in `missing` instead.
+pub async fn fetch_links(client: &Client, wiki: &WikiConfig, titles: &[String]) -> Result<FetchedLinks, String> {
+    let mut r#continue = PageLinksContinue { pl_continue: None, r#continue: None };
+    let mut collected = FetchedLinks::default();
+
+    loop {
+        // only links into the crawled namespaces can become edges of the graph
+        let mut url = wiki.url(&[
+            ("action", "query"),
+            ("format", "json"),
+            ("prop", "links"),
+            ("formatversion", "2"),
+            ("pllimit", "max"),
+            ("plnamespace", &wiki.namespace_param()),
+            ("titles", &titles.join("|")),
+        ]);
+
+        let PageLinksContinue { r#continue: cont, pl_continue } = r#continue;
+
+        // continue parameters to get the next page of results
+
+        if let Some(ref pl_continue) = pl_continue {
+            url.query_pairs_mut().append_pair("plcontinue", pl_continue.as_str());
+        }
+
+        if let Some(ref cont) = cont {
+            url.query_pairs_mut().append_pair("continue", cont.as_str());
+        }
+
+        println!("Sending request cont={:?} @ {}...", pl_continue, titles[0]);
+
+        let resp = client.get(url)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let resp: PageLinks = serde_json::from_str(&resp)
+            .map_err(|e| format!("malformed response: {}", e))?;
+
+        // some pages will have no links
+        let Some(links) = resp.query.and_then(|mut q| q.remove("pages")) else { break };
+
+        for page in links {
+            if page.missing {
+                collected.missing.insert(page.title);
+                continue;
+            }
+
+            let l = page.links
+                .unwrap_or_default()
+                .into_iter()
+                .map(|l| l.title);
+
+            collected.links.entry(page.title).or_default().extend(l);
+        }
+
+        // if there are more pages, continue requesting
+        match resp.r#continue {
+            Some(cont) => r#continue = cont,
+            None => break,
+        }
+    }
+
+    Ok(collected)
+}
@@ -0,0 +1,135 @@
+use sqlx::postgres::PgPoolOptions;
+use sqlx::query;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::Path;
+pub use wikilinks_core::{escape, unescape};
+use wikilinks_core::{load_snapshot, Source};
+
+/// One article of a snapshot: its id, title and the ids it links to
+/// (the `condensed_links` column).
+#[derive(Debug, Clone)]
+pub struct Article {
+    pub id: i32,
+    pub title: String,
+    pub links: Vec<i32>,
+}
+
+/// The graph of one wiki at one point in time, as written by the `snapshot` binary.
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub wiki: Option<String>,
+    pub taken_at: Option<String>,
+    pub articles: Vec<Article>,
+}
+
+impl Snapshot {
+    /// Write the `# wiki=... taken_at=...` header and one article per line as id,
+    /// title and the space-separated ids it links to, separated by tabs.
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut header = vec![];
+        if let Some(wiki) = &self.wiki { header.push(format!("wiki={}", wiki)); }
+        if let Some(taken_at) = &self.taken_at { header.push(format!("taken_at={}", taken_at)); }
+        if !header.is_empty() {
+            writeln!(out, "# {}", header.join(" "))?;
+        }
+
+        for article in &self.articles {
+            let links = article.links.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(" ");
+            writeln!(out, "{}\t{}\t{}", article.id, escape(&article.title), links)?;
+        }
+
+        Ok(())
+    }
+
+    /// The articles at most `hops` links away from `title`, following links in
+    /// both directions, and the links between them. `None` if there is no such title.
+    pub fn neighbourhood(&self, title: &str, hops: usize) -> Option<Snapshot> {
+        let start = self.articles.iter().position(|a| a.title == title)?;
+        let by_id = self.articles.iter().enumerate().map(|(i, a)| (a.id, i)).collect::<HashMap<_, _>>();
+
+        // backlinks, so the neighbourhood includes the articles linking to it
+        let mut incoming = vec![vec![]; self.articles.len()];
+        for (i, article) in self.articles.iter().enumerate() {
+            for link in article.links.iter().filter_map(|l| by_id.get(l)) {
+                incoming[*link].push(i);
+            }
+        }
+
+        let mut seen = HashSet::from([start]);
+        let mut frontier = vec![start];
+        for _ in 0..hops {
+            let mut next = vec![];
+            for &i in &frontier {
+                let outgoing = self.articles[i].links.iter().filter_map(|l| by_id.get(l).copied());
+                for j in outgoing.chain(incoming[i].iter().copied()) {
+                    if seen.insert(j) { next.push(j); }
+                }
+            }
+            frontier = next;
+        }
+
+        let ids = seen.iter().map(|&i| self.articles[i].id).collect::<HashSet<_>>();
+        let articles = self.articles
+            .iter()
+            .filter(|a| ids.contains(&a.id))
+            .map(|a| Article {
+                id: a.id,
+                title: a.title.clone(),
+                links: a.links.iter().copied().filter(|l| ids.contains(l)).collect(),
+            })
+            .collect();
+
+        Some(Snapshot { wiki: self.wiki.clone(), taken_at: self.taken_at.clone(), articles })
+    }
+}
+
+/// Load a graph the way the server does, from any [`Source`]: `db:WIKI` reads
+/// the wiki's current graph from the `DATABASE_URL` database,
+/// `postgres://...#WIKI` from another one (`enwiki` without `#WIKI`), and
+/// `snapshot:PATH` or a bare path a snapshot file.
+pub async fn load(source: &str) -> Result<Snapshot, Box<dyn Error + Send + Sync>> {
+    let source = match Source::parse(source) {
+        Ok(source) => source,
+        Err(_) => Source::Snapshot(source.into()),
+    };
+
+    let (url, wiki) = match source {
+        Source::Database { url, wiki } => (url, wiki),
+        Source::Snapshot(path) => return read(&path),
+    };
+
+    let pool = PgPoolOptions::new().connect(&url).await?;
+
+    let articles = query!("SELECT id, title, condensed_links FROM articles WHERE wiki = $1 ORDER BY id ASC", wiki)
+        .fetch_all(&pool)
+        .await?;
+
+    Ok(Snapshot {
+        wiki: Some(wiki),
+        taken_at: None,
+        articles: articles
+            .into_iter()
+            .map(|a| Article { id: a.id, title: a.title, links: a.condensed_links })
+            .collect(),
+    })
+}
+
+/// Read a snapshot file with the server's [`load_snapshot`]. Files without a
+/// header (e.g. written by `\copy`) are accepted, their `wiki` and `taken_at`
+/// are left empty.
+pub fn read(path: &Path) -> Result<Snapshot, Box<dyn Error + Send + Sync>> {
+    let snapshot = load_snapshot(path)?;
+    let id = |id: usize| i32::try_from(id).map_err(|_| format!("{}: id {} is too large", path.display(), id));
+
+    let articles = snapshot.rows
+        .into_iter()
+        .map(|(article, title, links)| {
+            let links = links.into_iter().map(id).collect::<Result<Vec<_>, _>>()?;
+            Ok(Article { id: id(article)?, title, links })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(Snapshot { wiki: snapshot.wiki, taken_at: snapshot.taken_at, articles })
+}
@@ -0,0 +1,61 @@
+use reqwest::Url;
+
+/// Which MediaWiki instance the scrapers crawl. Every row written by the
+/// scrapers is tagged with `id`, so several wikis can share one database.
+#[derive(Debug, Clone)]
+pub struct WikiConfig {
+    /// Short identifier stored in the `wiki` column, e.g. `enwiki` or `dewiktionary`.
+    pub id: String,
+    /// The wiki's `api.php` endpoint.
+    pub api_url: Url,
+    /// Namespaces whose pages become articles. Links are only followed into these.
+    pub namespaces: Vec<i32>,
+}
+
+impl Default for WikiConfig {
+    fn default() -> Self {
+        WikiConfig {
+            id: "enwiki".to_string(),
+            api_url: Url::parse("https://en.wikipedia.org/w/api.php").unwrap(),
+            namespaces: vec![0],
+        }
+    }
+}
+
+impl WikiConfig {
+    /// Defaults (English Wikipedia, main namespace) overridden by the `SCRAPER_WIKI`,
+    /// `SCRAPER_API_URL` and `SCRAPER_NAMESPACES` environment variables.
+    /// Namespaces are separated by `|` or `,`, e.g. `SCRAPER_NAMESPACES=0|14`.
+    pub fn from_env() -> Self {
+        let mut config = WikiConfig::default();
+
+        if let Ok(id) = std::env::var("SCRAPER_WIKI") {
+            config.id = id;
+        }
+
+        if let Ok(url) = std::env::var("SCRAPER_API_URL") {
+            config.api_url = Url::parse(&url).expect("SCRAPER_API_URL is not a valid URL");
+        }
+
+        if let Ok(namespaces) = std::env::var("SCRAPER_NAMESPACES") {
+            config.namespaces = namespaces
+                .split(['|', ','])
+                .map(|n| n.trim().parse().expect("SCRAPER_NAMESPACES must be numbers"))
+                .collect();
+        }
+
+        config
+    }
+
+    /// The API endpoint with `params` appended to the query string.
+    pub fn url(&self, params: &[(&str, &str)]) -> Url {
+        let mut url = self.api_url.clone();
+        url.query_pairs_mut().extend_pairs(params);
+        url
+    }
+
+    /// Namespaces in the `ns1|ns2` form the API expects.
+    pub fn namespace_param(&self) -> String {
+        self.namespaces.iter().map(|n| n.to_string()).collect::<Vec<_>>().join("|")
+    }
+}
@@ -0,0 +1,151 @@
+use reqwest::{StatusCode, Url};
+use scraper::client::{Client, ClientConfig, ClientError};
+use scraper::pagelinks::fetch_links;
+use scraper::wiki::WikiConfig;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// A canned HTTP response: status line, extra headers and body.
+type Canned = (&'static str, &'static str, &'static str);
+
+/// Serve `responses` in order (repeating the last one) on a local port and
+/// record the request line of every request received.
+async fn mock_server(responses: Vec<Canned>) -> (Url, Arc<Mutex<Vec<String>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = Url::parse(&format!("http://{}/w/api.php?action=query", listener.local_addr().unwrap())).unwrap();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+
+    let seen = Arc::clone(&requests);
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+
+            let mut buf = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).await.unwrap();
+                if n == 0 { break; }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+
+            let request_line = String::from_utf8_lossy(&buf).lines().next().unwrap_or_default().to_string();
+            let index = {
+                let mut seen = seen.lock().unwrap();
+                seen.push(request_line);
+                (seen.len() - 1).min(responses.len() - 1)
+            };
+
+            let (status, headers, body) = responses[index];
+            let resp = format!(
+                "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n{}\r\n{}",
+                status, body.len(), headers, body
+            );
+            stream.write_all(resp.as_bytes()).await.unwrap();
+        }
+    });
+
+    (url, requests)
+}
+
+fn config() -> ClientConfig {
+    ClientConfig {
+        requests_per_second: 1000.0,
+        burst: 10,
+        max_retries: 3,
+        base_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(50),
+        ..ClientConfig::default()
+    }
+}
+
+#[tokio::test]
+async fn retries_after_too_many_requests() {
+    let (url, requests) = mock_server(vec![
+        ("429 Too Many Requests", "retry-after: 1\r\n", ""),
+        ("200 OK", "", "{}"),
+    ]).await;
+
+    let client = Client::new(config());
+    let start = Instant::now();
+    let body = client.get(url).await.unwrap();
+
+    assert_eq!(body, "{}");
+    assert_eq!(requests.lock().unwrap().len(), 2);
+    assert!(start.elapsed() >= Duration::from_secs(1), "Retry-After was not honoured");
+}
+
+#[tokio::test]
+async fn retries_while_lagged() {
+    let lagged = r#"{"error":{"code":"maxlag","info":"Waiting for a database server: 7 seconds lagged."}}"#;
+    let (url, requests) = mock_server(vec![
+        ("200 OK", "retry-after: 0\r\n", lagged),
+        ("200 OK", "", r#"{"query":{}}"#),
+    ]).await;
+
+    let client = Client::new(ClientConfig { maxlag: Some(5), ..config() });
+    let body = client.get(url).await.unwrap();
+
+    assert_eq!(body, r#"{"query":{}}"#);
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].contains("maxlag=5"), "maxlag parameter missing: {}", requests[0]);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_retries() {
+    let (url, requests) = mock_server(vec![("503 Service Unavailable", "", "")]).await;
+
+    let client = Client::new(config());
+    let err = client.get(url).await.unwrap_err();
+
+    assert!(matches!(err, ClientError::Status(StatusCode::SERVICE_UNAVAILABLE)));
+    assert_eq!(requests.lock().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn does_not_retry_client_errors() {
+    let (url, requests) = mock_server(vec![("404 Not Found", "", "")]).await;
+
+    let client = Client::new(config());
+    let err = client.get(url).await.unwrap_err();
+
+    assert!(matches!(err, ClientError::Status(StatusCode::NOT_FOUND)));
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn rate_limits_requests() {
+    let (url, requests) = mock_server(vec![("200 OK", "", "{}")]).await;
+
+    // one request up front, then one every 100ms
+    let client = Client::new(ClientConfig { requests_per_second: 10.0, burst: 1, ..config() });
+    let start = Instant::now();
+    for _ in 0..5 {
+        client.get(url.clone()).await.unwrap();
+    }
+
+    assert_eq!(requests.lock().unwrap().len(), 5);
+    assert!(start.elapsed() >= Duration::from_millis(350), "requests were not rate limited");
+}
+
+#[tokio::test]
+async fn fetch_links_reports_missing_pages() {
+    let body = r#"{"batchcomplete":true,"query":{"pages":[
+        {"ns":0,"title":"A","links":[{"ns":0,"title":"B"}]},
+        {"ns":0,"title":"C"},
+        {"ns":0,"title":"Deleted","missing":true}
+    ]}}"#;
+    let (url, _) = mock_server(vec![("200 OK", "", body)]).await;
+
+    let wiki = WikiConfig { api_url: url, ..WikiConfig::default() };
+    let titles = ["A", "C", "Deleted"].map(String::from);
+    let fetched = fetch_links(&Client::new(config()), &wiki, &titles).await.unwrap();
+
+    assert_eq!(fetched.links.len(), 2);
+    assert_eq!(fetched.links["A"], vec!["B".to_string()]);
+    assert!(fetched.links["C"].is_empty());
+    assert!(fetched.missing.contains("Deleted") && fetched.missing.len() == 1);
+}
@@ -0,0 +1,156 @@
+use serde::Serialize;
+use crate::graph::Graph;
+
+/// One article as returned by `/article/{title}`.
+#[derive(Serialize)]
+pub struct ArticleDetail {
+    pub id: usize,
+    /// The title as stored, which may differ from the one asked for.
+    pub title: String,
+    /// The title asked for when it is a redirect to this article.
+    pub redirected_from: Option<String>,
+    /// Known redirects to the article, see [`Graph::redirects`].
+    pub redirects: Vec<String>,
+    pub redirect_target: bool,
+    pub out_degree: usize,
+    pub in_degree: usize,
+    /// A page of the articles it links to and that link to it, sorted by title.
+    pub links: Vec<String>,
+    pub backlinks: Vec<String>,
+    pub offset: usize,
+    pub limit: usize,
+    /// Articles in its strongly connected component, itself included. `None`
+    /// for new articles while the components are computed again.
+    pub component_size: Option<usize>,
+    /// `None` until the scores are computed.
+    pub pagerank: Option<f64>,
+}
+
+/// The article a title names: the exact title, then the title as MediaWiki
+/// normalises it (underscores as spaces, first letter upper case), then a
+/// known redirect. Returns the id and the redirect followed, if any.
+pub fn resolve(graph: &Graph, title: &str) -> Option<(usize, Option<String>)> {
+    if let Some(&id) = graph.title_map.get(title) {
+        return Some((id, None));
+    }
+
+    let normalised = normalise(title);
+    if let Some(&id) = graph.title_map.get(&normalised) {
+        return Some((id, None));
+    }
+
+    let redirect = [title, normalised.as_str()]
+        .into_iter()
+        .find_map(|t| graph.redirects.get(t).map(|&id| (id, t.to_string())));
+
+    redirect
+        .filter(|(id, _)| !graph.rev_title_map[*id].is_empty())
+        .map(|(id, t)| (id, Some(t)))
+}
+
+pub fn detail(graph: &Graph, id: usize, redirected_from: Option<String>, offset: usize, limit: usize) -> ArticleDetail {
+    let page = |ids: &[usize]| {
+        let mut titles = graph.titles(ids);
+        titles.sort();
+        titles.into_iter().skip(offset).take(limit).collect::<Vec<_>>()
+    };
+
+    let mut redirects = graph.redirects_to.get(&id).cloned().unwrap_or_default();
+    redirects.sort();
+
+    let components = graph.components.get();
+
+    ArticleDetail {
+        id,
+        title: graph.rev_title_map[id].clone(),
+        redirected_from,
+        redirect_target: !redirects.is_empty(),
+        redirects,
+        out_degree: graph.links.articles[id].len(),
+        in_degree: graph.links.backlinks[id].len(),
+        links: page(&graph.links.articles[id]),
+        backlinks: page(&graph.links.backlinks[id]),
+        offset,
+        limit,
+        component_size: components.of(id).map(|c| components.sizes[c as usize]),
+        pagerank: graph.scores.as_ref().and_then(|s| s.pagerank.get(id).copied()),
+    }
+}
+
+fn normalise(title: &str) -> String {
+    let title = title.replace('_', " ");
+    let title = title.trim();
+
+    let mut chars = title.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::components::Components;
+
+    /// "Zebra" links to the other four and "Ant" links back, so the two form
+    /// a component. "Old cow" used to be the title of "Cow".
+    fn graph() -> Graph {
+        let mut graph = Graph::from_links(vec![vec![1, 2, 3, 4], vec![0], vec![], vec![], vec![]]);
+        graph.rev_title_map = ["Zebra", "Ant", "Cow", "Albert Einstein", "Moth"].map(String::from).to_vec();
+        graph.title_map = graph.rev_title_map.iter().cloned().zip(0..).collect();
+        graph.redirects.insert("Old cow".to_string(), 2);
+        graph.redirects_to.insert(2, vec!["Old cow".to_string()]);
+        graph.components.replace(Arc::new(Components::new(&graph)));
+        graph
+    }
+
+    #[test]
+    fn resolve_tries_the_title_then_normalised_then_redirects() {
+        let graph = graph();
+        assert_eq!(resolve(&graph, "Zebra"), Some((0, None)));
+        assert_eq!(resolve(&graph, "albert_Einstein"), Some((3, None)));
+        assert_eq!(resolve(&graph, "  moth "), Some((4, None)));
+        assert_eq!(resolve(&graph, "Old cow"), Some((2, Some("Old cow".to_string()))));
+        assert_eq!(resolve(&graph, "old_cow"), Some((2, Some("Old cow".to_string()))));
+        assert_eq!(resolve(&graph, "Horse"), None);
+        assert_eq!(resolve(&graph, ""), None);
+    }
+
+    #[test]
+    fn resolve_skips_redirects_to_deleted_articles() {
+        let mut graph = graph();
+        graph.rev_title_map[2].clear();
+        assert_eq!(resolve(&graph, "Old cow"), None);
+    }
+
+    #[test]
+    fn detail_pages_links_by_title() {
+        let graph = graph();
+
+        let zebra = detail(&graph, 0, None, 1, 2);
+        assert_eq!(zebra.title, "Zebra");
+        assert_eq!((zebra.out_degree, zebra.in_degree), (4, 1));
+        assert_eq!(zebra.links, ["Ant", "Cow"]);
+        assert_eq!(zebra.backlinks, Vec::<String>::new());
+        assert_eq!(zebra.component_size, Some(2));
+
+        let cow = detail(&graph, 2, Some("Old cow".to_string()), 0, 10);
+        assert_eq!(cow.redirects, ["Old cow"]);
+        assert!(cow.redirect_target);
+        assert_eq!(cow.backlinks, ["Zebra"]);
+        assert_eq!(cow.component_size, Some(1));
+    }
+
+    #[test]
+    fn detail_leaves_out_what_is_not_computed() {
+        let graph = graph();
+        graph.components.replace(Arc::default());
+
+        let ant = detail(&graph, 1, None, 0, 10);
+        assert_eq!(ant.component_size, None);
+        assert_eq!(ant.pagerank, None);
+        assert!(!ant.redirect_target);
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use crate::graph::Graph;
+use wikilinks_core::{BreadthFirst, PathTree, Search};
+
+/// Most pairs in one batch.
+pub const MAX_PAIRS: usize = 100_000;
+
+/// A pair as `{"from": "A", "to": "B"}` or `["A", "B"]`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Pair {
+    Object { from: String, to: String },
+    Array(String, String),
+}
+
+impl From<Pair> for (String, String) {
+    fn from(pair: Pair) -> (String, String) {
+        match pair {
+            Pair::Object { from, to } | Pair::Array(from, to) => (from, to),
+        }
+    }
+}
+
+/// One line of the response, answers come in the order they are found.
+#[derive(Serialize)]
+struct Answer<'a> {
+    /// Position of the pair in the request.
+    index: usize,
+    from: &'a str,
+    to: &'a str,
+    /// `ok`, `not_found` when either title is not an article, or `unreachable`.
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<Vec<String>>,
+}
+
+/// The pairs of a `/batch` body: a JSON list of pairs, or one pair per line (NDJSON).
+pub fn parse(body: &str) -> Result<Vec<(String, String)>, String> {
+    if let Ok(pairs) = serde_json::from_str::<Vec<Pair>>(body) {
+        return Ok(pairs.into_iter().map(Into::into).collect());
+    }
+
+    body.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str::<Pair>(line)
+                .map(Into::into)
+                .map_err(|e| format!("line {}: {}", i + 1, e))
+        })
+        .collect()
+}
+
+/// Answer every pair on every core and send the answers to `tx` as NDJSON.
+/// Pairs are grouped by start, and a start with several ends searches the
+/// graph once for all of them. Stops early when the receiver is dropped,
+/// i.e. the client went away.
+pub fn run(graph: &Graph, pairs: &[(String, String)], tx: mpsc::Sender<Bytes>) {
+    let mut unknown = vec![];
+    let mut by_start = HashMap::<usize, Vec<(usize, usize)>>::new();
+    for (index, (from, to)) in pairs.iter().enumerate() {
+        match (graph.title_map.get(from), graph.title_map.get(to)) {
+            (Some(&start), Some(&end)) => by_start.entry(start).or_default().push((index, end)),
+            _ => unknown.push(Answer { index, from, to, status: "not_found", path: None }),
+        }
+    }
+
+    if !unknown.is_empty() && tx.blocking_send(lines(&unknown)).is_err() { return; }
+
+    let groups = by_start.into_iter().collect::<Vec<_>>();
+    let next = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+
+    let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1).min(groups.len());
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                while !stopped.load(Ordering::Relaxed) {
+                    let Some((start, ends)) = groups.get(next.fetch_add(1, Ordering::Relaxed)) else { break };
+
+                    let answers = answer(graph, pairs, *start, ends);
+                    if tx.blocking_send(lines(&answers)).is_err() {
+                        stopped.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+    });
+}
+
+/// Answer the pairs sharing `start`. A single end stops searching once found,
+/// several share a full search.
+fn answer<'a>(graph: &Graph, pairs: &'a [(String, String)], start: usize, ends: &[(usize, usize)]) -> Vec<Answer<'a>> {
+    let components = graph.components.get();
+    let reachable = ends.iter().map(|&(_, end)| components.reachable(start, end)).collect::<Vec<_>>();
+
+    let tree = (reachable.iter().filter(|&&r| r).count() > 1).then(|| PathTree::new(&graph.links, start));
+
+    ends.iter()
+        .zip(reachable)
+        .map(|(&(index, end), reachable)| {
+            let path = match &tree {
+                _ if !reachable => None,
+                Some(tree) => tree.path_to(end),
+                None => BreadthFirst.path(&graph.links, start, end),
+            };
+
+            let (from, to) = &pairs[index];
+            Answer {
+                index,
+                from,
+                to,
+                status: if path.is_some() { "ok" } else { "unreachable" },
+                path: path.map(|p| graph.titles(&p)),
+            }
+        })
+        .collect()
+}
+
+fn lines(answers: &[Answer]) -> Bytes {
+    let mut out = Vec::new();
+    for answer in answers {
+        serde_json::to_writer(&mut out, answer).unwrap();
+        out.push(b'\n');
+    }
+    Bytes::from(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn pair(from: &str, to: &str) -> (String, String) {
+        (from.to_string(), to.to_string())
+    }
+
+    #[test]
+    fn parse_reads_a_json_list_of_either_shape() {
+        let pairs = parse(r#"[["A", "B"], {"from": "C", "to": "D"}]"#).unwrap();
+        assert_eq!(pairs, [pair("A", "B"), pair("C", "D")]);
+    }
+
+    #[test]
+    fn parse_reads_one_pair_per_line() {
+        let pairs = parse("[\"A\", \"B\"]\n\n{\"from\": \"C\", \"to\": \"D\"}\n").unwrap();
+        assert_eq!(pairs, [pair("A", "B"), pair("C", "D")]);
+    }
+
+    #[test]
+    fn parse_names_the_malformed_line() {
+        let error = parse("[\"A\", \"B\"]\n[\"C\"]\n").unwrap_err();
+        assert!(error.starts_with("line 2:"), "{error}");
+        assert!(parse("{\"from\": \"A\"}").is_err());
+    }
+
+    /// The messages sent by `run`, each as its answers.
+    fn run_all(graph: &Graph, pairs: &[(String, String)]) -> Vec<Vec<Value>> {
+        let (tx, mut rx) = mpsc::channel(pairs.len() + 1);
+        run(graph, pairs, tx);
+
+        let mut messages = vec![];
+        while let Ok(bytes) = rx.try_recv() {
+            let text = std::str::from_utf8(&bytes).unwrap();
+            messages.push(text.lines().map(|line| serde_json::from_str(line).unwrap()).collect());
+        }
+        messages
+    }
+
+    #[test]
+    fn run_answers_every_pair_with_its_status() {
+        // 3 -> 0 -> 1 -> 2
+        let graph = Graph::from_links(vec![vec![1], vec![2], vec![], vec![0]]);
+        let pairs = [pair("0", "2"), pair("2", "0"), pair("0", "nope"), pair("3", "2")];
+
+        let mut answers = run_all(&graph, &pairs).concat();
+        answers.sort_by_key(|a| a["index"].as_u64());
+
+        assert_eq!(answers.len(), 4);
+        assert_eq!(answers[0]["status"], "ok");
+        assert_eq!(answers[0]["path"], serde_json::json!(["0", "1", "2"]));
+        assert_eq!(answers[1]["status"], "unreachable");
+        assert!(answers[1].get("path").is_none());
+        assert_eq!(answers[2]["status"], "not_found");
+        assert_eq!(answers[2]["from"], "0");
+        assert_eq!(answers[3]["path"], serde_json::json!(["3", "0", "1", "2"]));
+    }
+
+    #[test]
+    fn run_answers_a_shared_start_from_one_search() {
+        // 0 -> 1 -> 2 and 0 -> 3, asked of 0 three times
+        let graph = Graph::from_links(vec![vec![1, 3], vec![2], vec![], vec![]]);
+        let pairs = [pair("0", "2"), pair("1", "2"), pair("0", "3"), pair("0", "1")];
+
+        let messages = run_all(&graph, &pairs);
+        assert_eq!(messages.len(), 2);
+
+        let shared = messages.iter().find(|m| m.len() == 3).unwrap();
+        let paths = shared.iter().map(|a| (a["index"].as_u64().unwrap(), a["path"].clone())).collect::<HashMap<_, _>>();
+        assert_eq!(paths[&0], serde_json::json!(["0", "1", "2"]));
+        assert_eq!(paths[&2], serde_json::json!(["0", "3"]));
+        assert_eq!(paths[&3], serde_json::json!(["0", "1"]));
+    }
+}
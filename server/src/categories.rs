@@ -0,0 +1,134 @@
+use std::collections::{HashMap, VecDeque};
+use sqlx::{query, PgPool};
+
+/// Subcategories deeper than this are never followed. The hierarchy of a large
+/// wiki reaches nearly every category from a few levels down.
+pub const MAX_DEPTH: usize = 4;
+
+/// The categories of every article and the hierarchy between them, loaded from
+/// `article_categories` and `categories` (see the `categories` scraper).
+/// Categories are numbered in the order they were first seen.
+#[derive(Debug, Default)]
+pub struct Categories {
+    /// Full title of every category, with the namespace prefix.
+    pub titles: Vec<String>,
+    /// Both the full title and the name without the prefix map to a category.
+    index: HashMap<String, u32>,
+    /// Categories of every article id. Articles added by live updates have none.
+    of_article: Vec<Vec<u32>>,
+    children: Vec<Vec<u32>>,
+}
+
+impl Categories {
+    pub async fn load(conn: &PgPool, wiki: &str) -> Result<Categories, sqlx::Error> {
+        let articles = query!(
+            "SELECT a.id, c.categories FROM article_categories c JOIN articles a ON a.wiki = c.wiki AND a.title = c.title WHERE c.wiki = $1",
+            wiki
+        )
+            .fetch_all(conn)
+            .await?;
+
+        let hierarchy = query!("SELECT title, parents FROM categories WHERE wiki = $1", wiki)
+            .fetch_all(conn)
+            .await?;
+
+        let mut categories = Categories::default();
+
+        let size = articles.iter().map(|a| a.id as usize + 1).max().unwrap_or(0);
+        categories.of_article = vec![vec![]; size];
+        for article in articles {
+            let ids = article.categories.into_iter().map(|c| categories.intern(c)).collect();
+            categories.of_article[article.id as usize] = ids;
+        }
+
+        for category in hierarchy {
+            categories.add_parents(category.title, category.parents);
+        }
+
+        Ok(categories)
+    }
+
+    fn add_parents(&mut self, title: String, parents: Vec<String>) {
+        let child = self.intern(title);
+        for parent in parents {
+            let parent = self.intern(parent);
+            if parent != child {
+                self.children[parent as usize].push(child);
+            }
+        }
+    }
+
+    fn intern(&mut self, title: String) -> u32 {
+        if let Some(&id) = self.index.get(&title) { return id; }
+
+        let id = self.titles.len() as u32;
+        if let Some((_, name)) = title.split_once(':') {
+            self.index.entry(name.to_string()).or_insert(id);
+        }
+        self.index.insert(title.clone(), id);
+        self.titles.push(title);
+        self.children.push(vec![]);
+        id
+    }
+
+    /// A category by its full title (`Category:Physics`) or its name (`Physics`).
+    pub fn find(&self, name: &str) -> Option<u32> {
+        self.index.get(name).copied()
+    }
+
+    /// Categories of an article.
+    pub fn of(&self, id: usize) -> &[u32] {
+        self.of_article.get(id).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn titles_of(&self, id: usize) -> Vec<String> {
+        self.of(id).iter().map(|&c| self.titles[c as usize].clone()).collect()
+    }
+
+    /// For every category, whether it is one of `roots` or a subcategory at most
+    /// `depth` levels below one.
+    pub fn within(&self, roots: &[u32], depth: usize) -> Vec<bool> {
+        let mut within = vec![false; self.titles.len()];
+        let mut queue = VecDeque::new();
+
+        for &root in roots {
+            if !within[root as usize] {
+                within[root as usize] = true;
+                queue.push_back((root, 0));
+            }
+        }
+
+        while let Some((curr, level)) = queue.pop_front() {
+            if level == depth.min(MAX_DEPTH) { continue; }
+
+            for &child in &self.children[curr as usize] {
+                if within[child as usize] { continue; }
+
+                within[child as usize] = true;
+                queue.push_back((child, level + 1));
+            }
+        }
+
+        within
+    }
+
+    /// Whether the article is in one of the categories marked in `within`.
+    pub fn is_member(&self, id: usize, within: &[bool]) -> bool {
+        self.of(id).iter().any(|&c| within[c as usize])
+    }
+
+    /// Categories for tests: the full category titles of every article id, and
+    /// (category, parents) pairs for the hierarchy.
+    #[cfg(test)]
+    pub(crate) fn from_titles(of_article: &[&[&str]], hierarchy: &[(&str, &[&str])]) -> Categories {
+        let mut categories = Categories::default();
+        categories.of_article = of_article
+            .iter()
+            .map(|titles| titles.iter().map(|t| categories.intern(t.to_string())).collect())
+            .collect();
+        for (title, parents) in hierarchy {
+            categories.add_parents(title.to_string(), parents.iter().map(|p| p.to_string()).collect());
+        }
+        categories
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::VecDeque;
+use serde::Serialize;
+use crate::clusters;
+use crate::graph::Graph;
+
+/// Probability of following a link rather than jumping to a random article.
+const DAMPING: f64 = 0.85;
+/// PageRank stops once the ranks change by less than this in total.
+const TOLERANCE: f64 = 1e-8;
+const MAX_ITERATIONS: usize = 100;
+
+/// Centrality and cluster of every article, indexed by article id like
+/// [`Graph::links`]. In-degree is not stored, it is the number of backlinks.
+#[derive(Debug, Clone)]
+pub struct Scores {
+    /// Sums to 1 over the articles of the graph.
+    pub pagerank: Vec<f64>,
+    /// Estimated number of shortest paths between two articles passing through
+    /// each article, see [`betweenness`].
+    pub betweenness: Vec<f64>,
+    /// Community of each article, see [`clusters::label_propagation`].
+    pub cluster: Vec<u32>,
+    /// Number of articles in each cluster.
+    pub cluster_sizes: Vec<usize>,
+    /// When the scores were computed (UTC, RFC 3339), if they were stored.
+    pub computed_at: Option<String>,
+}
+
+/// The scores of one article, as returned by `/scores` and `/top`.
+#[derive(Serialize)]
+pub struct ArticleScores {
+    pub title: String,
+    pub pagerank: f64,
+    pub in_degree: usize,
+    pub out_degree: usize,
+    pub betweenness: f64,
+    pub cluster: u32,
+}
+
+/// The score `/top` ranks articles by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Ranking {
+    PageRank,
+    InDegree,
+    Betweenness,
+}
+
+impl Ranking {
+    pub fn parse(s: &str) -> Option<Ranking> {
+        match s {
+            "pagerank" => Some(Ranking::PageRank),
+            "in_degree" => Some(Ranking::InDegree),
+            "betweenness" => Some(Ranking::Betweenness),
+            _ => None,
+        }
+    }
+}
+
+impl Scores {
+    /// The `limit` articles with the highest score by `ranking`, ties by title.
+    /// Selects them without sorting the others, and only looks up the details
+    /// of those.
+    pub fn top(&self, graph: &Graph, ranking: Ranking, limit: usize) -> Vec<ArticleScores> {
+        let score = |id: usize| match ranking {
+            Ranking::PageRank => self.pagerank_of(id),
+            Ranking::InDegree => graph.links.backlinks[id].len() as f64,
+            Ranking::Betweenness => self.betweenness_of(id),
+        };
+        let order = |&a: &usize, &b: &usize| {
+            score(b).total_cmp(&score(a)).then_with(|| graph.rev_title_map[a].cmp(&graph.rev_title_map[b]))
+        };
+
+        if limit == 0 { return vec![]; }
+
+        let mut ids = (0..graph.rev_title_map.len())
+            .filter(|&id| !graph.rev_title_map[id].is_empty())
+            .collect::<Vec<_>>();
+        if limit < ids.len() {
+            ids.select_nth_unstable_by(limit - 1, order);
+            ids.truncate(limit);
+        }
+        ids.sort_unstable_by(order);
+
+        ids.into_iter().map(|id| self.article(graph, id)).collect()
+    }
+
+    pub fn article(&self, graph: &Graph, id: usize) -> ArticleScores {
+        ArticleScores {
+            title: graph.rev_title_map[id].clone(),
+            pagerank: self.pagerank_of(id),
+            in_degree: graph.links.backlinks[id].len(),
+            out_degree: graph.links.articles[id].len(),
+            betweenness: self.betweenness_of(id),
+            cluster: self.cluster_of(id),
+        }
+    }
+
+    // live updates add articles after the scores are computed, those have
+    // no scores and no cluster until the next computation
+
+    pub fn pagerank_of(&self, id: usize) -> f64 {
+        self.pagerank.get(id).copied().unwrap_or(0.0)
+    }
+
+    pub fn betweenness_of(&self, id: usize) -> f64 {
+        self.betweenness.get(id).copied().unwrap_or(0.0)
+    }
+
+    pub fn cluster_of(&self, id: usize) -> u32 {
+        self.cluster.get(id).copied().unwrap_or(clusters::NO_CLUSTER)
+    }
+}
+
+/// Betweenness samples from `WIKILINKS_BETWEENNESS_SAMPLES`, default 64. More
+/// samples give better estimates, each one is a search over the whole graph.
+pub fn configured_samples() -> usize {
+    std::env::var("WIKILINKS_BETWEENNESS_SAMPLES").ok().and_then(|s| s.parse().ok()).unwrap_or(64)
+}
+
+/// Compute every score and the clusters of the graph. Betweenness is estimated
+/// from `samples` breadth-first searches, 0 skips it.
+pub fn compute(graph: &Graph, samples: usize) -> Scores {
+    let cluster = clusters::label_propagation(graph);
+
+    Scores {
+        pagerank: pagerank(graph),
+        betweenness: betweenness(graph, samples),
+        cluster_sizes: clusters::sizes(&cluster),
+        cluster,
+        computed_at: None,
+    }
+}
+
+/// PageRank by power iteration. Articles without links spread their rank over
+/// every article, as if they linked to all of them.
+pub fn pagerank(graph: &Graph) -> Vec<f64> {
+    let exists = (0..graph.links.articles.len())
+        .map(|id| !graph.rev_title_map[id].is_empty())
+        .collect::<Vec<_>>();
+    let n = exists.iter().filter(|&&e| e).count() as f64;
+    if n == 0.0 { return vec![0.0; graph.links.articles.len()]; }
+
+    let mut rank = exists.iter().map(|&e| if e { 1.0 / n } else { 0.0 }).collect::<Vec<_>>();
+    let mut next = vec![0.0; rank.len()];
+
+    for _ in 0..MAX_ITERATIONS {
+        let dangling = (0..rank.len())
+            .filter(|&id| graph.links.articles[id].is_empty())
+            .map(|id| rank[id])
+            .sum::<f64>();
+        let base = (1.0 - DAMPING) / n + DAMPING * dangling / n;
+
+        for (id, r) in next.iter_mut().enumerate() {
+            *r = if exists[id] { base } else { 0.0 };
+        }
+
+        for (id, links) in graph.links.articles.iter().enumerate() {
+            if links.is_empty() { continue; }
+
+            let share = DAMPING * rank[id] / links.len() as f64;
+            for &link in links {
+                next[link] += share;
+            }
+        }
+
+        let change = rank.iter().zip(&next).map(|(a, b)| (a - b).abs()).sum::<f64>();
+        std::mem::swap(&mut rank, &mut next);
+
+        if change < TOLERANCE { break; }
+    }
+
+    rank
+}
+
+/// Betweenness centrality estimated with Brandes' algorithm from `samples`
+/// source articles spread evenly over the ids, scaled up to all sources.
+/// The searches run on every core.
+pub fn betweenness(graph: &Graph, samples: usize) -> Vec<f64> {
+    let size = graph.links.articles.len();
+    let sources = (0..size).filter(|&id| !graph.rev_title_map[id].is_empty()).collect::<Vec<_>>();
+    if samples == 0 || sources.is_empty() { return vec![0.0; size]; }
+
+    let step = (sources.len() as f64 / samples as f64).max(1.0);
+    let sampled = (0..samples.min(sources.len()))
+        .map(|i| sources[(i as f64 * step) as usize])
+        .collect::<Vec<_>>();
+
+    let threads = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1).min(sampled.len());
+    let partials = std::thread::scope(|scope| {
+        let handles = sampled
+            .chunks(sampled.len().div_ceil(threads))
+            .map(|chunk| scope.spawn(move || {
+                let mut search = Brandes::new(size);
+                let mut centrality = vec![0.0; size];
+                for &source in chunk {
+                    search.accumulate(graph, source, &mut centrality);
+                }
+                centrality
+            }))
+            .collect::<Vec<_>>();
+
+        handles.into_iter().map(|h| h.join().unwrap()).collect::<Vec<_>>()
+    });
+
+    let scale = sources.len() as f64 / sampled.len() as f64;
+    let mut centrality = vec![0.0; size];
+    for partial in partials {
+        for (total, c) in centrality.iter_mut().zip(partial) {
+            *total += c * scale;
+        }
+    }
+
+    centrality
+}
+
+/// Buffers for one single-source step of Brandes' algorithm, reused between sources.
+struct Brandes {
+    distance: Vec<u32>,
+    /// Number of shortest paths from the source.
+    paths: Vec<f64>,
+    dependency: Vec<f64>,
+    order: Vec<usize>,
+    queue: VecDeque<usize>,
+}
+
+impl Brandes {
+    fn new(size: usize) -> Brandes {
+        Brandes {
+            distance: vec![u32::MAX; size],
+            paths: vec![0.0; size],
+            dependency: vec![0.0; size],
+            order: Vec::new(),
+            queue: VecDeque::new(),
+        }
+    }
+
+    /// Add the dependencies of `source` on every other article to `centrality`.
+    fn accumulate(&mut self, graph: &Graph, source: usize, centrality: &mut [f64]) {
+        // only reset what the last search touched
+        for &id in &self.order {
+            self.distance[id] = u32::MAX;
+            self.paths[id] = 0.0;
+            self.dependency[id] = 0.0;
+        }
+        self.order.clear();
+
+        self.distance[source] = 0;
+        self.paths[source] = 1.0;
+        self.queue.push_back(source);
+
+        while let Some(curr) = self.queue.pop_front() {
+            self.order.push(curr);
+
+            for &link in &graph.links.articles[curr] {
+                if self.distance[link] == u32::MAX {
+                    self.distance[link] = self.distance[curr] + 1;
+                    self.queue.push_back(link);
+                }
+                if self.distance[link] == self.distance[curr] + 1 {
+                    self.paths[link] += self.paths[curr];
+                }
+            }
+        }
+
+        // predecessors are the backlinks one step closer to the source
+        for &curr in self.order.iter().rev() {
+            for &prev in &graph.links.backlinks[curr] {
+                if self.distance[prev] != u32::MAX && self.distance[prev] + 1 == self.distance[curr] {
+                    self.dependency[prev] += self.paths[prev] / self.paths[curr] * (1.0 + self.dependency[curr]);
+                }
+            }
+
+            if curr != source {
+                centrality[curr] += self.dependency[curr];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn pagerank_sums_to_one_with_dangling_articles() {
+        // 2 links nowhere, 4 neither links nor is linked to
+        let graph = Graph::from_links(vec![vec![1], vec![2], vec![], vec![2], vec![]]);
+        let rank = pagerank(&graph);
+
+        assert!((rank.iter().sum::<f64>() - 1.0).abs() < 1e-6, "{:?}", rank);
+        assert!(rank[2] > rank[1] && rank[1] > rank[0]);
+        assert_close(&[rank[0]], &[rank[3]]);
+        assert_close(&[rank[0]], &[rank[4]]);
+    }
+
+    #[test]
+    fn pagerank_is_even_on_a_cycle() {
+        let graph = Graph::from_links(vec![vec![1], vec![2], vec![3], vec![0]]);
+        assert_close(&pagerank(&graph), &[0.25; 4]);
+    }
+
+    #[test]
+    fn betweenness_on_a_path() {
+        // 0 -> 1 -> 2 -> 3: 1 is on the paths from 0 to 2 and 3, 2 on those
+        // from 0 and 1 to 3
+        let graph = Graph::from_links(vec![vec![1], vec![2], vec![3], vec![]]);
+        assert_close(&betweenness(&graph, 4), &[0.0, 2.0, 2.0, 0.0]);
+    }
+
+    #[test]
+    fn betweenness_on_a_star() {
+        // the centre links to and from four leaves, and is on the paths
+        // between every ordered pair of them
+        let graph = Graph::from_links(vec![vec![1, 2, 3, 4], vec![0], vec![0], vec![0], vec![0]]);
+        assert_close(&betweenness(&graph, 5), &[12.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_close(&betweenness(&graph, 0), &[0.0; 5]);
+    }
+
+    #[test]
+    fn top_picks_the_highest_scores_with_ties_by_title() {
+        // 2 is linked from everything, 3 from 0 and 1, the rest from one article
+        let mut graph = Graph::from_links(vec![vec![2, 3], vec![2, 3], vec![0], vec![2], vec![1, 2], vec![]]);
+        graph.rev_title_map[5].clear();
+        let scores = compute(&graph, 6);
+
+        let titles = |ranking, limit| scores.top(&graph, ranking, limit).into_iter().map(|s| s.title).collect::<Vec<_>>();
+
+        assert_eq!(titles(Ranking::InDegree, 2), vec!["2", "3"]);
+        assert_eq!(titles(Ranking::InDegree, 10), vec!["2", "3", "0", "1", "4"]);
+        assert_eq!(titles(Ranking::PageRank, 1), vec!["2"]);
+        assert!(titles(Ranking::Betweenness, 0).is_empty());
+
+        let mut by_pagerank = scores.top(&graph, Ranking::PageRank, 10);
+        assert_eq!(by_pagerank.len(), 5);
+        assert!(by_pagerank.windows(2).all(|w| w[0].pagerank >= w[1].pagerank));
+        by_pagerank.truncate(3);
+        assert_eq!(by_pagerank.iter().map(|s| s.title.as_str()).collect::<Vec<_>>(), titles(Ranking::PageRank, 3));
+    }
+
+    #[test]
+    fn betweenness_splits_between_shortest_paths() {
+        // two shortest paths from 0 to 3, one through each of 1 and 2
+        let graph = Graph::from_links(vec![vec![1, 2], vec![3], vec![3], vec![]]);
+        assert_close(&betweenness(&graph, 4), &[0.0, 0.5, 0.5, 0.0]);
+    }
+}
@@ -0,0 +1,252 @@
+use std::io::{BufRead, BufWriter, IsTerminal, Write};
+use wikilinks::graph::{Graph, Source};
+use wikilinks::tree::{self, Format};
+use wikilinks_core::{BreadthFirst, DepthFirst, Search};
+
+const USAGE: &str = "usage: wikilinks [--graph SOURCE] [COMMAND ARGS...]
+
+commands:
+  path A B              shortest path from A to B
+  dfs A B               a path from A to B by depth-first search
+  neighbors A           articles A links to
+  backlinks A           articles linking to A
+  search PATTERN [N]    N (default 10) titles matching a SQL LIKE pattern
+  stats                 size of the graph
+  tree A [--format ndjson|binary] [--max-distance N] [-o PATH]
+                        every article reachable from A with its distance
+                        and parent, as the server's /tree writes it
+  help                  this message
+
+Without a command, reads commands from stdin, one per line. Titles with
+spaces are quoted: path \"Albert Einstein\" Physics
+
+SOURCE is db:WIKI, postgres://...#WIKI, snapshot:PATH or a snapshot file,
+default db:$WIKILINKS_WIKI (enwiki).";
+
+/// Ask the graph questions from a terminal or a script, without a server.
+/// Uses the same graph and searches as the server.
+fn main() {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    let source = match args.iter().position(|a| a == "--graph") {
+        Some(i) if i + 1 < args.len() => {
+            let source = args.remove(i + 1);
+            args.remove(i);
+            source
+        }
+        Some(_) => exit_with_usage(),
+        None => format!("db:{}", std::env::var("WIKILINKS_WIKI").unwrap_or_else(|_| "enwiki".to_string())),
+    };
+
+    if args.first().is_some_and(|a| a == "help" || a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+
+    // a bare path is a snapshot
+    let source = match Source::parse(&source) {
+        Ok(source) => source,
+        Err(_) if std::path::Path::new(&source).exists() => Source::Snapshot(source.into()),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start runtime");
+    runtime.block_on(async {
+        let graph = Graph::load("cli", source.clone())
+            .await
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to load graph from {}: {}", source, e);
+                std::process::exit(1);
+            });
+
+        if !args.is_empty() {
+            match run(&graph, &args, &mut std::io::stdout().lock()).await {
+                Ok(()) => {}
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            }
+            return;
+        }
+
+        repl(&graph).await;
+    });
+}
+
+/// Read commands from stdin until it ends or `quit`, printing a prompt when
+/// stdin is a terminal.
+async fn repl(graph: &Graph) {
+    let interactive = std::io::stdin().is_terminal();
+    if interactive {
+        eprintln!("Loaded {} articles from {}, type help for commands", graph.title_map.len(), graph.source);
+    }
+
+    let mut lines = std::io::stdin().lock().lines();
+    loop {
+        if interactive {
+            print!("> ");
+            std::io::stdout().flush().unwrap();
+        }
+
+        let Some(Ok(line)) = lines.next() else { break };
+        let args = match split(&line) {
+            Ok(args) => args,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+
+        match args.first().map(String::as_str) {
+            None => continue,
+            Some("quit" | "exit") => break,
+            Some("help") => println!("{}", USAGE),
+            Some(_) => {
+                if let Err(e) = run(graph, &args, &mut std::io::stdout().lock()).await {
+                    eprintln!("{}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Run one command, writing what it prints to `out`.
+async fn run(graph: &Graph, args: &[String], out: &mut impl Write) -> Result<(), String> {
+    if args.first().is_some_and(|a| a == "tree") {
+        return write_tree(graph, &args[1..], out);
+    }
+
+    let output = answer(graph, args).await?;
+    writeln!(out, "{}", output).map_err(|e| e.to_string())
+}
+
+/// `tree A [--format ndjson|binary] [--max-distance N] [-o PATH]`: the
+/// shortest-path tree from A in a [`Format`], to PATH or `out`.
+fn write_tree(graph: &Graph, args: &[String], out: &mut impl Write) -> Result<(), String> {
+    let mut args = args.iter();
+    let mut title = None;
+    let mut format = Format::Ndjson;
+    let mut max_distance = u32::MAX;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = args.next().and_then(|f| Format::parse(f)).ok_or("--format takes ndjson or binary")?;
+            }
+            "--max-distance" => {
+                max_distance = args.next().and_then(|n| n.parse().ok()).ok_or("--max-distance takes a number")?;
+            }
+            "-o" | "--output" => output = Some(args.next().ok_or("-o takes a path")?),
+            _ if title.is_none() => title = Some(arg),
+            _ => return Err(format!("unexpected argument: {}\n\n{}", arg, USAGE)),
+        }
+    }
+
+    let title = title.ok_or_else(|| format!("tree needs an article\n\n{}", USAGE))?;
+    let start = graph.title_map.get(title).copied().ok_or_else(|| format!("{} is not in the graph", title))?;
+
+    let result = match output {
+        Some(path) => std::fs::File::create(path)
+            .and_then(|file| tree::write(graph, start, format, max_distance, &mut BufWriter::new(file))),
+        None => tree::write(graph, start, format, max_distance, &mut BufWriter::new(out)),
+    };
+    result.map_err(|e| format!("Failed to write tree: {}", e))
+}
+
+/// Run one command, returning what to print.
+async fn answer(graph: &Graph, args: &[String]) -> Result<String, String> {
+    let article = |title: &String| graph.title_map.get(title).copied().ok_or_else(|| format!("{} is not in the graph", title));
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["path", _, _] | ["dfs", _, _] => {
+            let (start, end) = (article(&args[1])?, article(&args[2])?);
+
+            let path = if args[0] == "path" {
+                match graph.components.get().reachable(start, end) {
+                    true => BreadthFirst.path(&graph.links, start, end),
+                    false => None,
+                }
+            } else {
+                DepthFirst.path(&graph.links, start, end)
+            };
+
+            path.map(|p| graph.titles(&p).join("\n")).ok_or_else(|| format!("no path from {} to {}", args[1], args[2]))
+        }
+
+        ["neighbors", _] | ["backlinks", _] => {
+            let id = article(&args[1])?;
+            let links = if args[0] == "neighbors" { &graph.links.articles[id] } else { &graph.links.backlinks[id] };
+
+            let mut titles = graph.titles(links);
+            titles.sort();
+            Ok(titles.join("\n"))
+        }
+
+        ["search", pattern, rest @ ..] if rest.len() <= 1 => {
+            let limit = match rest.first() {
+                Some(n) => n.parse().map_err(|_| format!("not a number: {}", n))?,
+                None => 10,
+            };
+
+            let titles = graph.search(pattern, limit).await.map_err(|e| e.to_string())?;
+            Ok(titles.join("\n"))
+        }
+
+        ["stats"] => {
+            let links = graph.links.articles.iter().map(Vec::len).sum::<usize>();
+            let components = graph.components.get();
+            let largest = components.largest(1).first().map_or(0, |&c| components.sizes[c as usize]);
+            let most_linked = graph.title_map.values().max_by_key(|&&id| (graph.links.backlinks[id].len(), std::cmp::Reverse(id)));
+
+            let mut stats = vec![
+                format!("source\t{}", graph.source),
+                format!("taken_at\t{}", graph.taken_at.as_deref().unwrap_or("")),
+                format!("articles\t{}", graph.title_map.len()),
+                format!("links\t{}", links),
+                format!("components\t{}", components.count()),
+                format!("largest_component\t{}", largest),
+            ];
+            if let Some(&id) = most_linked {
+                stats.push(format!("most_linked\t{} ({} backlinks)", graph.rev_title_map[id], graph.links.backlinks[id].len()));
+            }
+            Ok(stats.join("\n"))
+        }
+
+        _ => Err(format!("unknown command: {}\n\n{}", args.join(" "), USAGE)),
+    }
+}
+
+/// Split a REPL line into words. Double quotes group words with spaces, and
+/// `\` escapes the next character.
+fn split(line: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word = None::<String>;
+    let mut quoted = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            '"' => {
+                quoted = !quoted;
+                word.get_or_insert_with(String::new);
+            }
+            c if c.is_whitespace() && !quoted => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quoted { return Err("unterminated quote".to_string()); }
+    words.extend(word);
+    Ok(words)
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    std::process::exit(2);
+}
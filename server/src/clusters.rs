@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::centrality::Scores;
+use crate::graph::Graph;
+
+/// Cluster of ids without an article.
+pub const NO_CLUSTER: u32 = u32::MAX;
+/// Label propagation stops after this many rounds, or once fewer than
+/// `1 / STABLE` of the articles change their label in a round.
+const MAX_ROUNDS: usize = 20;
+const STABLE: usize = 1000;
+
+/// A cluster as returned by `/cluster`.
+#[derive(Serialize)]
+pub struct ClusterInfo {
+    pub cluster: u32,
+    pub size: usize,
+    /// Its articles with the highest PageRank.
+    pub top_members: Vec<String>,
+    /// Its articles with the most links to and from other clusters.
+    pub bridges: Vec<Bridge>,
+}
+
+#[derive(Serialize)]
+pub struct Bridge {
+    pub title: String,
+    /// Links to and from articles in other clusters.
+    pub outside_links: usize,
+    /// The other clusters it is linked with, most links first.
+    pub clusters: Vec<u32>,
+}
+
+/// Communities found by label propagation over the links in both directions:
+/// every article starts in a cluster of its own and repeatedly joins the
+/// cluster most of its neighbours are in. Clusters are numbered by size,
+/// 0 is the largest.
+pub fn label_propagation(graph: &Graph) -> Vec<u32> {
+    let n = graph.links.articles.len();
+    let exists = |id: usize| !graph.rev_title_map[id].is_empty();
+
+    let mut label = (0..n as u32).collect::<Vec<_>>();
+    let mut neighbours = Vec::<u32>::new();
+
+    // visit articles in a fixed pseudo-random order, a sorted order lets the
+    // labels of low ids flood the graph
+    let mut order = (0..n).filter(|&id| exists(id)).collect::<Vec<_>>();
+    let mut state = 0x9e3779b97f4a7c15u64;
+    for i in (1..order.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        order.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+
+    for _ in 0..MAX_ROUNDS {
+        let mut changed = 0;
+
+        for &id in &order {
+            neighbours.clear();
+            neighbours.extend(graph.links.articles[id].iter().chain(&graph.links.backlinks[id]).map(|&l| label[l]));
+            if neighbours.is_empty() { continue; }
+            neighbours.sort_unstable();
+
+            // the most common label, keeping the current one on a tie
+            let (mut best, mut best_count) = (label[id], 0);
+            for run in neighbours.chunk_by(|a, b| a == b) {
+                if run.len() > best_count || (run.len() == best_count && run[0] == label[id]) {
+                    (best, best_count) = (run[0], run.len());
+                }
+            }
+
+            if best != label[id] {
+                label[id] = best;
+                changed += 1;
+            }
+        }
+
+        if changed <= order.len() / STABLE { break; }
+    }
+
+    // number the clusters by size
+    let mut sizes = HashMap::<u32, usize>::new();
+    for &id in &order {
+        *sizes.entry(label[id]).or_default() += 1;
+    }
+
+    let mut by_size = sizes.into_iter().collect::<Vec<_>>();
+    by_size.sort_by_key(|&(label, size)| (std::cmp::Reverse(size), label));
+    let numbers = by_size.iter().enumerate().map(|(i, &(label, _))| (label, i as u32)).collect::<HashMap<_, _>>();
+
+    (0..n).map(|id| if exists(id) { numbers[&label[id]] } else { NO_CLUSTER }).collect()
+}
+
+/// Number of articles in every cluster.
+pub fn sizes(cluster: &[u32]) -> Vec<usize> {
+    let count = cluster.iter().filter(|&&c| c != NO_CLUSTER).max().map_or(0, |&c| c as usize + 1);
+
+    let mut sizes = vec![0; count];
+    for &c in cluster.iter().filter(|&&c| c != NO_CLUSTER) {
+        sizes[c as usize] += 1;
+    }
+
+    sizes
+}
+
+/// The `limit` top members and bridges of a cluster.
+pub fn info(graph: &Graph, scores: &Scores, cluster: u32, limit: usize) -> ClusterInfo {
+    let members = (0..graph.links.articles.len())
+        .filter(|&id| scores.cluster_of(id) == cluster)
+        .collect::<Vec<_>>();
+
+    let mut top = members.clone();
+    top.sort_by(|&a, &b| scores.pagerank_of(b).total_cmp(&scores.pagerank_of(a)));
+    top.truncate(limit);
+
+    let mut bridges = members
+        .iter()
+        .map(|&id| {
+            let mut outside = HashMap::<u32, usize>::new();
+            for &other in graph.links.articles[id].iter().chain(&graph.links.backlinks[id]) {
+                let c = scores.cluster_of(other);
+                if c != cluster && c != NO_CLUSTER {
+                    *outside.entry(c).or_default() += 1;
+                }
+            }
+            (id, outside)
+        })
+        .filter(|(_, outside)| !outside.is_empty())
+        .map(|(id, outside)| {
+            let mut clusters = outside.into_iter().collect::<Vec<_>>();
+            clusters.sort_by_key(|&(c, links)| (std::cmp::Reverse(links), c));
+
+            Bridge {
+                title: graph.rev_title_map[id].clone(),
+                outside_links: clusters.iter().map(|(_, links)| links).sum(),
+                clusters: clusters.into_iter().map(|(c, _)| c).take(5).collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    bridges.sort_by(|a, b| b.outside_links.cmp(&a.outside_links).then_with(|| a.title.cmp(&b.title)));
+    bridges.truncate(limit);
+
+    ClusterInfo {
+        cluster,
+        size: members.len(),
+        top_members: graph.titles(&top),
+        bridges,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every article of `ids` linking to every other one.
+    fn clique(links: &mut [Vec<usize>], ids: std::ops::Range<usize>) {
+        for id in ids.clone() {
+            links[id].extend(ids.clone().filter(|&other| other != id));
+        }
+    }
+
+    #[test]
+    fn splits_two_cliques_joined_by_a_bridge() {
+        // a clique of 6 and one of 5, with a single link from 5 to 6
+        let mut links = vec![vec![]; 11];
+        clique(&mut links, 0..6);
+        clique(&mut links, 6..11);
+        links[5].push(6);
+
+        let cluster = label_propagation(&Graph::from_links(links));
+
+        assert!(cluster[..6].iter().all(|&c| c == 0), "{:?}", cluster);
+        assert!(cluster[6..].iter().all(|&c| c == 1), "{:?}", cluster);
+        assert_eq!(sizes(&cluster), vec![6, 5]);
+    }
+
+    #[test]
+    fn leaves_out_ids_without_an_article() {
+        let mut links = vec![vec![]; 4];
+        clique(&mut links, 0..3);
+        let mut graph = Graph::from_links(links);
+        graph.rev_title_map[3].clear();
+
+        let cluster = label_propagation(&graph);
+        assert_eq!(cluster, vec![0, 0, 0, NO_CLUSTER]);
+        assert_eq!(sizes(&cluster), vec![3]);
+    }
+}
@@ -0,0 +1,105 @@
+use serde::Serialize;
+use crate::graph::Graph;
+use wikilinks_core::{BreadthFirst, Search};
+
+/// The shortest path between two titles in an older and a newer graph, and
+/// the links along them that only exist in one of the two.
+#[derive(Serialize)]
+pub struct PathDiff {
+    pub old: GraphPath,
+    pub new: GraphPath,
+    /// Links on the new path that the old graph did not have.
+    pub appeared: Vec<Edge>,
+    /// Links on the old path that the new graph no longer has.
+    pub disappeared: Vec<Edge>,
+}
+
+#[derive(Serialize)]
+pub struct GraphPath {
+    pub graph: String,
+    pub taken_at: Option<String>,
+    /// `None` if either title is missing or there is no path.
+    pub path: Option<Vec<String>>,
+    /// Endpoints that are not articles in this graph.
+    pub missing: Vec<String>,
+}
+
+#[derive(Serialize, PartialEq)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+}
+
+pub fn compare(old: &Graph, new: &Graph, start: &str, end: &str) -> PathDiff {
+    let old_path = path(old, start, end);
+    let new_path = path(new, start, end);
+
+    let appeared = edges_missing_from(new_path.path.as_deref(), old);
+    let disappeared = edges_missing_from(old_path.path.as_deref(), new);
+
+    PathDiff { old: old_path, new: new_path, appeared, disappeared }
+}
+
+fn path(graph: &Graph, start: &str, end: &str) -> GraphPath {
+    let missing = [start, end]
+        .into_iter()
+        .filter(|t| !graph.title_map.contains_key(*t))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    let path = match (graph.title_map.get(start), graph.title_map.get(end)) {
+        (Some(&start), Some(&end)) => BreadthFirst.path(&graph.links, start, end).map(|p| graph.titles(&p)),
+        _ => None,
+    };
+
+    GraphPath { graph: graph.name.clone(), taken_at: graph.taken_at.clone(), path, missing }
+}
+
+/// Consecutive pairs of `path` that are not links in `other`, either because
+/// the link is gone or because one of the articles does not exist there.
+fn edges_missing_from(path: Option<&[String]>, other: &Graph) -> Vec<Edge> {
+    let Some(path) = path else { return vec![] };
+
+    path.windows(2)
+        .filter(|pair| {
+            let (from, to) = (other.title_map.get(&pair[0]), other.title_map.get(&pair[1]));
+            !matches!((from, to), (Some(&from), Some(&to)) if other.links.has_edge(from, to))
+        })
+        .map(|pair| Edge { from: pair[0].clone(), to: pair[1].clone() })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(edges: &[Edge]) -> Vec<(&str, &str)> {
+        edges.iter().map(|e| (e.from.as_str(), e.to.as_str())).collect()
+    }
+
+    #[test]
+    fn links_on_only_one_path_are_listed() {
+        // 0 -> 1 -> 2 before, then 0 links to 2 directly and 1 -> 2 is gone
+        let old = Graph::from_links(vec![vec![1], vec![2], vec![]]);
+        let new = Graph::from_links(vec![vec![1, 2], vec![], vec![]]);
+
+        let diff = compare(&old, &new, "0", "2");
+        assert_eq!(diff.old.path, Some(vec!["0".to_string(), "1".to_string(), "2".to_string()]));
+        assert_eq!(diff.new.path, Some(vec!["0".to_string(), "2".to_string()]));
+        assert_eq!(edges(&diff.appeared), [("0", "2")]);
+        assert_eq!(edges(&diff.disappeared), [("1", "2")]);
+    }
+
+    #[test]
+    fn missing_articles_have_no_path() {
+        let old = Graph::from_links(vec![vec![1], vec![]]);
+        let new = Graph::from_links(vec![vec![1], vec![2], vec![]]);
+
+        let diff = compare(&old, &new, "0", "2");
+        assert_eq!(diff.old.path, None);
+        assert_eq!(diff.old.missing, ["2"]);
+        assert!(diff.new.missing.is_empty());
+        assert_eq!(edges(&diff.appeared), [("1", "2")]);
+        assert!(diff.disappeared.is_empty());
+    }
+}
@@ -0,0 +1,441 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use arc_swap::ArcSwap;
+use serde::Serialize;
+use crate::graph::Graph;
+
+/// [`Components::update`] gives up on a link removed inside a component when
+/// no other path is found within this many articles.
+const MAX_DETOUR: usize = 100_000;
+
+/// Strongly connected components of a link graph and the condensation DAG
+/// between them. Components are numbered in the order Tarjan's algorithm
+/// finishes them, which is a reverse topological order: every link between two
+/// components goes from a higher number to a lower one.
+#[derive(Debug, Clone, Default)]
+pub struct Components {
+    /// Component of every article id. Ids without an article get a component of
+    /// their own, of size 0.
+    pub component: Vec<u32>,
+    /// Number of articles in each component.
+    pub sizes: Vec<usize>,
+    /// Links between components, each listed once.
+    pub dag: Vec<Vec<u32>>,
+    /// Set when live updates changed the graph in a way [`Components::update`]
+    /// can't follow, until the components are computed again. Stale components
+    /// may be missing new articles and don't rule out any path.
+    pub stale: bool,
+}
+
+/// The components of a graph, replaced without copying the graph when they are
+/// computed again after live updates. A clone is a cell of its own.
+#[derive(Default)]
+pub struct ComponentsCell(ArcSwap<Components>);
+
+impl ComponentsCell {
+    pub fn new(components: Components) -> ComponentsCell {
+        ComponentsCell(ArcSwap::from_pointee(components))
+    }
+
+    pub fn get(&self) -> Arc<Components> {
+        self.0.load_full()
+    }
+
+    pub fn replace(&self, components: Arc<Components>) -> Arc<Components> {
+        self.0.swap(components)
+    }
+
+    /// [`Components::reachable`] for a request: answered right away when it
+    /// can be, otherwise the condensation is searched on a blocking thread.
+    pub async fn reachable(&self, from: usize, to: usize) -> bool {
+        let components = self.get();
+        if let Some(reachable) = components.reachable_at_once(from, to) {
+            return reachable;
+        }
+
+        tokio::task::spawn_blocking(move || components.reachable(from, to))
+            .await
+            .unwrap_or(true)
+    }
+}
+
+impl Clone for ComponentsCell {
+    fn clone(&self) -> ComponentsCell {
+        ComponentsCell(ArcSwap::new(self.get()))
+    }
+}
+
+/// A component as returned by `/components` and `/component`.
+#[derive(Serialize)]
+pub struct ComponentInfo {
+    pub component: u32,
+    pub size: usize,
+    /// Components it links to and that link to it.
+    pub out_components: usize,
+    pub in_components: usize,
+    /// Some of its articles, at most the requested number.
+    pub members: Vec<String>,
+}
+
+impl Components {
+    /// Tarjan's algorithm, iterative so the deep searches of a large graph
+    /// don't overflow the stack.
+    pub fn new(graph: &Graph) -> Components {
+        const UNVISITED: u32 = u32::MAX;
+
+        let articles = &graph.links.articles;
+        let n = articles.len();
+        let mut index = vec![UNVISITED; n];
+        let mut lowlink = vec![0u32; n];
+        let mut on_stack = vec![false; n];
+        let mut component = vec![0u32; n];
+        let mut sizes = vec![];
+        let mut stack = vec![];
+        let mut next_index = 0u32;
+
+        // (article, position in its links) for every article being searched
+        let mut calls = Vec::<(usize, usize)>::new();
+
+        for root in 0..n {
+            if index[root] != UNVISITED { continue; }
+
+            calls.push((root, 0));
+            index[root] = next_index;
+            lowlink[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some(&mut (curr, ref mut pos)) = calls.last_mut() {
+                if let Some(&link) = articles[curr].get(*pos) {
+                    *pos += 1;
+
+                    if index[link] == UNVISITED {
+                        index[link] = next_index;
+                        lowlink[link] = next_index;
+                        next_index += 1;
+                        stack.push(link);
+                        on_stack[link] = true;
+                        calls.push((link, 0));
+                    } else if on_stack[link] {
+                        lowlink[curr] = lowlink[curr].min(index[link]);
+                    }
+                    continue;
+                }
+
+                // every link of curr is done
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[curr]);
+                }
+
+                if lowlink[curr] == index[curr] {
+                    let id = sizes.len() as u32;
+                    let mut size = 0;
+                    loop {
+                        let member = stack.pop().unwrap();
+                        on_stack[member] = false;
+                        component[member] = id;
+                        if !graph.rev_title_map[member].is_empty() { size += 1; }
+                        if member == curr { break; }
+                    }
+                    sizes.push(size);
+                }
+            }
+        }
+
+        let mut dag = vec![vec![]; sizes.len()];
+        for (id, links) in articles.iter().enumerate() {
+            for &link in links {
+                if component[id] != component[link] {
+                    dag[component[id] as usize].push(component[link]);
+                }
+            }
+        }
+        for links in &mut dag {
+            links.sort_unstable();
+            links.dedup();
+        }
+
+        Components { component, sizes, dag, stale: false }
+    }
+
+    /// Component of an article, `None` for articles added since the components
+    /// went stale.
+    pub fn of(&self, id: usize) -> Option<u32> {
+        self.component.get(id).copied()
+    }
+
+    /// Follow a change to article `id`, already applied to the graph, that
+    /// replaced its links `old_links` and its title, if it `had_title`. Links
+    /// that can't merge or split components only change the DAG and sizes;
+    /// returns false for any other change; the components are wrong then and
+    /// have to be computed again.
+    pub fn update(&mut self, graph: &Graph, id: usize, old_links: &[usize], had_title: bool) -> bool {
+        if id >= self.component.len() {
+            return self.add(graph, id);
+        }
+        // articles the change added would be numbered after id's component,
+        // breaking the order of the links to them
+        if graph.links.len() > self.component.len() { return false; }
+
+        let c = self.component[id];
+        match (had_title, !graph.rev_title_map[id].is_empty()) {
+            (false, true) => self.sizes[c as usize] += 1,
+            (true, false) => self.sizes[c as usize] -= 1,
+            _ => {}
+        }
+
+        let old = old_links.iter().copied().collect::<HashSet<_>>();
+        let new = graph.links.articles[id].iter().copied().collect::<HashSet<_>>();
+
+        for &to in new.difference(&old) {
+            let other = self.component[to];
+            if other == c { continue; }
+
+            // links go from higher numbers to lower ones; one the other way
+            // may close a cycle
+            if other > c { return false; }
+            if let Err(i) = self.dag[c as usize].binary_search(&other) {
+                self.dag[c as usize].insert(i, other);
+            }
+        }
+
+        for &to in old.difference(&new) {
+            let other = self.component[to];
+            if other == c {
+                if to != id && !self.detour(graph, id, to) { return false; }
+                continue;
+            }
+
+            match self.still_linked(graph, id, to) {
+                Some(true) => {}
+                Some(false) => self.dag[c as usize].retain(|&o| o != other),
+                None => return false,
+            }
+        }
+
+        true
+    }
+
+    /// Number a new article and the ids without an article it links to, which
+    /// only it links to. Returns false when other articles link to it too.
+    fn add(&mut self, graph: &Graph, id: usize) -> bool {
+        if graph.links.backlinks[id].iter().any(|&from| from != id) { return false; }
+
+        let first = self.component.len();
+        self.component.resize(graph.links.len(), 0);
+
+        for new in (first..graph.links.len()).filter(|&new| new != id) {
+            self.component[new] = self.sizes.len() as u32;
+            self.sizes.push(0);
+            self.dag.push(vec![]);
+        }
+
+        let c = self.sizes.len() as u32;
+        self.component[id] = c;
+        self.sizes.push(!graph.rev_title_map[id].is_empty() as usize);
+
+        let mut links = graph.links.articles[id].iter().map(|&l| self.component[l]).filter(|&o| o != c).collect::<Vec<_>>();
+        links.sort_unstable();
+        links.dedup();
+        self.dag.push(links);
+
+        true
+    }
+
+    /// Whether `from` can still reach `to` through articles of their
+    /// component, after a link between them was removed.
+    fn detour(&self, graph: &Graph, from: usize, to: usize) -> bool {
+        let c = self.component[from];
+        let mut seen = HashSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(curr) = queue.pop_front() {
+            for &next in &graph.links.articles[curr] {
+                if next == to { return true; }
+                if self.component[next] != c || !seen.insert(next) { continue; }
+                if seen.len() > MAX_DETOUR { return false; }
+                queue.push_back(next);
+            }
+        }
+
+        false
+    }
+
+    /// Whether the component of `from` still links to the component of `to`
+    /// after the link between them was removed, `None` when that would take
+    /// going through the members of both.
+    fn still_linked(&self, graph: &Graph, from: usize, to: usize) -> Option<bool> {
+        let (c, other) = (self.component[from], self.component[to]);
+
+        if graph.links.articles[from].iter().any(|&l| self.component[l] == other) { return Some(true); }
+        if self.alone(graph, from) { return Some(false); }
+
+        if graph.links.backlinks[to].iter().any(|&b| self.component[b] == c) { return Some(true); }
+        if self.alone(graph, to) { return Some(false); }
+
+        None
+    }
+
+    /// Whether an article is the only member of its component. A component of
+    /// several articles is a cycle, so all of them have links and titles.
+    fn alone(&self, graph: &Graph, id: usize) -> bool {
+        graph.rev_title_map[id].is_empty() || self.sizes[self.component[id] as usize] == 1
+    }
+
+    /// Number of components with at least one article.
+    pub fn count(&self) -> usize {
+        self.sizes.iter().filter(|&&s| s > 0).count()
+    }
+
+    /// The `limit` largest components, largest first.
+    pub fn largest(&self, limit: usize) -> Vec<u32> {
+        let mut ids = (0..self.sizes.len() as u32).filter(|&c| self.sizes[c as usize] > 0).collect::<Vec<_>>();
+        ids.sort_by_key(|&c| (std::cmp::Reverse(self.sizes[c as usize]), c));
+        ids.truncate(limit);
+        ids
+    }
+
+    /// Describe `components`, with up to `members` titles of each.
+    pub fn info(&self, graph: &Graph, components: &[u32], members: usize) -> Vec<ComponentInfo> {
+        let mut infos = components
+            .iter()
+            .map(|&c| ComponentInfo {
+                component: c,
+                size: self.sizes[c as usize],
+                out_components: self.dag[c as usize].len(),
+                in_components: self.dag.iter().filter(|links| links.binary_search(&c).is_ok()).count(),
+                members: vec![],
+            })
+            .collect::<Vec<_>>();
+
+        let positions = components.iter().enumerate().map(|(i, &c)| (c, i)).collect::<HashMap<_, _>>();
+        for (id, title) in graph.rev_title_map.iter().enumerate() {
+            if title.is_empty() { continue; }
+
+            if let Some(i) = self.of(id).and_then(|c| positions.get(&c).copied()) {
+                if infos[i].members.len() < members {
+                    infos[i].members.push(title.clone());
+                }
+            }
+        }
+
+        infos
+    }
+
+    /// Whether there is a path from `from` to `to`, when that is known in
+    /// constant time: both are in the same component, or `from`'s component
+    /// is numbered below `to`'s so the numbering rules it out. Stale
+    /// components answer true.
+    pub fn reachable_at_once(&self, from: usize, to: usize) -> Option<bool> {
+        if self.stale { return Some(true); }
+
+        let (from, to) = (self.component[from], self.component[to]);
+        if from == to { return Some(true); }
+        if from < to { return Some(false); }
+        None
+    }
+
+    /// Whether there is a path from `from` to `to`. Only the cases of
+    /// [`Components::reachable_at_once`] take constant time; otherwise this
+    /// searches the condensation DAG, skipping components numbered below
+    /// `to`'s, which takes as long as the components between them.
+    pub fn reachable(&self, from: usize, to: usize) -> bool {
+        if let Some(reachable) = self.reachable_at_once(from, to) {
+            return reachable;
+        }
+
+        let (from, to) = (self.component[from], self.component[to]);
+
+        // most components are single articles, so only remember the few visited
+        let mut visited = HashSet::from([from]);
+        let mut stack = vec![from];
+
+        while let Some(curr) = stack.pop() {
+            for &next in &self.dag[curr as usize] {
+                if next == to { return true; }
+                if next < to || !visited.insert(next) { continue; }
+
+                stack.push(next);
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0 and 1 link to each other, as do 3 and 4. 2 sits between the two
+    /// pairs and 5 links into the first one.
+    fn graph() -> Graph {
+        Graph::from_links(vec![vec![1], vec![0, 2], vec![3], vec![4], vec![3], vec![0]])
+    }
+
+    #[test]
+    fn numbers_components_in_reverse_topological_order() {
+        let graph = graph();
+        let components = graph.components.get();
+        let c = &components.component;
+
+        assert_eq!(components.count(), 4);
+        assert_eq!(c[0], c[1]);
+        assert_eq!(c[3], c[4]);
+        assert_eq!(components.sizes[c[0] as usize], 2);
+        assert_eq!(components.sizes[c[2] as usize], 1);
+
+        // Tarjan's algorithm finishes a component after every one it links to
+        assert!(c[5] > c[0] && c[0] > c[2] && c[2] > c[3]);
+        for (from, links) in graph.links.articles.iter().enumerate() {
+            for &to in links {
+                assert!(c[from] >= c[to], "{} -> {}", from, to);
+            }
+        }
+        assert_eq!(components.dag[c[1] as usize], vec![c[2]]);
+    }
+
+    #[test]
+    fn reachable_follows_the_condensation() {
+        let components = graph().components.get();
+
+        assert!(components.reachable(0, 1) && components.reachable(1, 0));
+        assert!(components.reachable(5, 4));
+        assert!(components.reachable(0, 3));
+        assert!(!components.reachable(3, 0));
+        assert!(!components.reachable(2, 5));
+
+        // only the same component and the numbering are answered at once
+        assert_eq!(components.reachable_at_once(0, 1), Some(true));
+        assert_eq!(components.reachable_at_once(3, 0), Some(false));
+        assert_eq!(components.reachable_at_once(5, 4), None);
+    }
+
+    #[tokio::test]
+    async fn searches_the_condensation_on_a_blocking_thread() {
+        let graph = graph();
+
+        assert!(graph.components.reachable(5, 4).await);
+        assert!(graph.components.reachable(0, 3).await);
+        assert!(!graph.components.reachable(2, 5).await);
+    }
+
+    #[test]
+    fn reachable_skips_only_components_numbered_below_the_target() {
+        // 0 -> 1 -> 2 and 0 -> 3, where 3 is finished before 1 and 2 and so
+        // numbered below them: searching from 0 for 2 must not stop at 3
+        let graph = Graph::from_links(vec![vec![3, 1], vec![2], vec![], vec![]]);
+        let components = graph.components.get();
+        let c = &components.component;
+        assert!(c[3] < c[2]);
+
+        assert!(components.reachable(0, 2));
+        assert!(!components.reachable(3, 2));
+        assert!(!components.reachable(1, 3));
+
+        let stale = Components { stale: true, ..(*components).clone() };
+        assert!(stale.reachable(1, 3));
+    }
+}
@@ -0,0 +1,231 @@
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+use hyper::service::service_fn;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpListener;
+use tokio::sync::{watch, Semaphore};
+use tokio_rustls::TlsAcceptor;
+use crate::graph::Registry;
+use crate::service;
+
+/// How many connections to keep open and for how long.
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    /// Connections served at once. Further clients wait in the listen backlog.
+    pub max_connections: usize,
+    /// Close a connection after this long without reading or writing anything.
+    pub idle_timeout: Duration,
+    /// How long shutting down waits for requests in flight.
+    pub drain_timeout: Duration,
+}
+
+impl Limits {
+    /// From `WIKILINKS_MAX_CONNECTIONS` (default 1024), and
+    /// `WIKILINKS_IDLE_TIMEOUT` and `WIKILINKS_DRAIN_TIMEOUT` in seconds
+    /// (default 60 and 30).
+    pub fn configured() -> Limits {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> T {
+            std::env::var(name).ok().and_then(|s| s.parse().ok()).unwrap_or(default)
+        }
+
+        Limits {
+            max_connections: var("WIKILINKS_MAX_CONNECTIONS", 1024).max(1),
+            idle_timeout: Duration::from_secs(var("WIKILINKS_IDLE_TIMEOUT", 60)),
+            drain_timeout: Duration::from_secs(var("WIKILINKS_DRAIN_TIMEOUT", 30)),
+        }
+    }
+}
+
+/// Serve HTTP/1.1 and HTTP/2 on the listener until `shutdown` completes, over
+/// TLS with an acceptor. HTTP/2 without TLS needs prior knowledge (h2c), the
+/// protocol is told apart by the connection preface.
+///
+/// On shutdown the listener closes and every connection is asked to close
+/// once its requests are answered: HTTP/1.1 connections close after the
+/// current response, HTTP/2 ones get a GOAWAY and finish their open streams.
+/// Returns when they have all closed or after `limits.drain_timeout`.
+pub async fn serve(
+    listener: TcpListener,
+    registry: Arc<Registry>,
+    limits: Limits,
+    tls: Option<TlsAcceptor>,
+    shutdown: impl Future<Output = ()>,
+) {
+    let builder = Arc::new(auto::Builder::new(TokioExecutor::new()));
+    let permits = Arc::new(Semaphore::new(limits.max_connections));
+    let (stop, stopped) = watch::channel(false);
+
+    tokio::pin!(shutdown);
+    loop {
+        // wait for a free slot before accepting, so clients over the limit
+        // queue in the kernel instead of holding a socket here
+        let permit = tokio::select! {
+            permit = Arc::clone(&permits).acquire_owned() => permit.expect("the semaphore is never closed"),
+            _ = &mut shutdown => break,
+        };
+
+        let stream = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    // usually out of file descriptors, give some a chance to close
+                    eprintln!("Failed to accept a connection: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+            _ = &mut shutdown => break,
+        };
+
+        let builder = Arc::clone(&builder);
+        let registry = Arc::clone(&registry);
+        let stopped = stopped.clone();
+
+        let tls = tls.clone();
+
+        tokio::spawn(async move {
+            let _permit = permit;
+            let (stream, activity) = Watched::new(stream);
+
+            let Some(tls) = tls else {
+                return serve_connection(stream, &builder, registry, &activity, limits.idle_timeout, stopped).await;
+            };
+
+            // a client that never finishes the handshake is idle too
+            match tokio::time::timeout(limits.idle_timeout, tls.accept(stream)).await {
+                Ok(Ok(stream)) => serve_connection(stream, &builder, registry, &activity, limits.idle_timeout, stopped).await,
+                Ok(Err(e)) => eprintln!("TLS handshake failed: {}", e),
+                Err(_) => {}
+            }
+        });
+    }
+
+    drop(listener);
+    let open = limits.max_connections - permits.available_permits();
+    println!("Shutting down, waiting for {} connections", open);
+
+    stop.send_replace(true);
+    // every permit is back once every connection has closed
+    let all = u32::try_from(limits.max_connections).unwrap_or(u32::MAX);
+    if tokio::time::timeout(limits.drain_timeout, permits.acquire_many(all)).await.is_err() {
+        let open = limits.max_connections - permits.available_permits();
+        eprintln!("Gave up on {} connections after {:?}", open, limits.drain_timeout);
+    }
+}
+
+/// Serve requests on one connection until it closes, closing it gracefully
+/// when idle or when the server stops.
+async fn serve_connection<S>(
+    io: S,
+    builder: &auto::Builder<TokioExecutor>,
+    registry: Arc<Registry>,
+    activity: &Activity,
+    idle_timeout: Duration,
+    mut stopped: watch::Receiver<bool>,
+) where S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let svc = service_fn(|req| service::service(req, Arc::clone(&registry)));
+    let conn = builder.serve_connection(TokioIo::new(io), svc);
+    tokio::pin!(conn);
+
+    // after a graceful shutdown the connection only has to finish
+    let mut closing = false;
+    loop {
+        let idle_at = activity.last() + idle_timeout;
+
+        tokio::select! {
+            result = conn.as_mut() => {
+                if let Err(err) = result {
+                    eprintln!("Server error: {:?}", err);
+                }
+                break;
+            }
+            _ = tokio::time::sleep_until(idle_at.into()), if !closing => {
+                if activity.last().elapsed() >= idle_timeout {
+                    conn.as_mut().graceful_shutdown();
+                    closing = true;
+                }
+            }
+            _ = stopped.changed(), if !closing => {
+                conn.as_mut().graceful_shutdown();
+                closing = true;
+            }
+        }
+    }
+}
+
+/// A stream that records when it last read or wrote anything, for the idle
+/// timeout. A request still being computed doesn't count as idle to hyper
+/// anyway: closing an idle connection waits for its requests.
+struct Watched<S> {
+    inner: S,
+    activity: Arc<Activity>,
+}
+
+struct Activity {
+    opened: Instant,
+    /// Milliseconds after `opened`.
+    last: AtomicU64,
+}
+
+impl Activity {
+    fn touch(&self) {
+        self.last.store(self.opened.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    fn last(&self) -> Instant {
+        self.opened + Duration::from_millis(self.last.load(Ordering::Relaxed))
+    }
+}
+
+impl<S> Watched<S> {
+    fn new(inner: S) -> (Watched<S>, Arc<Activity>) {
+        let activity = Arc::new(Activity { opened: Instant::now(), last: AtomicU64::new(0) });
+        (Watched { inner, activity: Arc::clone(&activity) }, activity)
+    }
+
+    fn touch_on<T>(&self, poll: Poll<io::Result<T>>) -> Poll<io::Result<T>> {
+        if let Poll::Ready(Ok(_)) = poll {
+            self.activity.touch();
+        }
+        poll
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Watched<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        self.touch_on(poll)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Watched<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write(cx, buf);
+        self.touch_on(poll)
+    }
+
+    fn poll_write_vectored(mut self: Pin<&mut Self>, cx: &mut Context<'_>, bufs: &[io::IoSlice<'_>]) -> Poll<io::Result<usize>> {
+        let poll = Pin::new(&mut self.inner).poll_write_vectored(cx, bufs);
+        self.touch_on(poll)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,93 @@
+use std::sync::OnceLock;
+use hyper::header::{self, HeaderMap, HeaderValue};
+
+/// Origins allowed to call the server, read from `WIKILINKS_CORS_ORIGINS` once.
+///
+/// The variable lists them separated by commas, e.g.
+/// `https://example.com,http://localhost:5173`. Unset allows every origin,
+/// empty allows none.
+enum Origins {
+    Any,
+    Listed(Vec<String>),
+}
+
+impl Origins {
+    fn parse(var: Option<&str>) -> Origins {
+        match var {
+            None => Origins::Any,
+            Some(origins) => Origins::Listed(
+                origins.split(',').map(str::trim).filter(|o| !o.is_empty()).map(String::from).collect(),
+            ),
+        }
+    }
+
+    fn from_env() -> &'static Origins {
+        static ORIGINS: OnceLock<Origins> = OnceLock::new();
+        ORIGINS.get_or_init(|| Origins::parse(std::env::var("WIKILINKS_CORS_ORIGINS").ok().as_deref()))
+    }
+
+    fn add_headers(&self, headers: &mut HeaderMap, origin: Option<&HeaderValue>) {
+        let allowed = match self {
+            Origins::Any => Some(HeaderValue::from_static("*")),
+            Origins::Listed(origins) => {
+                headers.append(header::VARY, HeaderValue::from_static("origin"));
+
+                origin
+                    .filter(|o| origins.iter().any(|allowed| o.as_bytes() == allowed.as_bytes()))
+                    .cloned()
+            }
+        };
+
+        if let Some(allowed) = allowed {
+            headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, allowed);
+        }
+    }
+}
+
+/// Add the CORS headers for a request from `origin` to a response.
+///
+/// A listed origin is sent back in `Access-Control-Allow-Origin`, others get
+/// no CORS headers, see [`Origins`].
+pub fn apply(headers: &mut HeaderMap, origin: Option<&HeaderValue>) {
+    Origins::from_env().add_headers(headers, origin);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(origins: Option<&str>, origin: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let origin = origin.map(|o| HeaderValue::from_str(o).unwrap());
+        Origins::parse(origins).add_headers(&mut headers, origin.as_ref());
+        headers
+    }
+
+    #[test]
+    fn unset_allows_every_origin() {
+        let headers = headers(None, Some("https://example.com"));
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert!(!headers.contains_key(header::VARY));
+    }
+
+    #[test]
+    fn a_listed_origin_is_sent_back() {
+        let headers = headers(Some("https://example.com, http://localhost:5173"), Some("http://localhost:5173"));
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "http://localhost:5173");
+        assert_eq!(headers[header::VARY], "origin");
+    }
+
+    #[test]
+    fn other_origins_get_no_cors_headers() {
+        for (origins, origin) in [
+            ("https://example.com", Some("https://evil.com")),
+            ("https://example.com", None),
+            ("", Some("https://example.com")),
+            (",", Some("")),
+        ] {
+            let headers = headers(Some(origins), origin);
+            assert!(!headers.contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN), "{origins} {origin:?}");
+            assert_eq!(headers[header::VARY], "origin");
+        }
+    }
+}
@@ -0,0 +1,671 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use arc_swap::ArcSwap;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{query, PgPool};
+use wikilinks_core::LinkGraph;
+pub use wikilinks_core::{load_snapshot, ArticleRow, Snapshot, Source};
+use crate::categories::Categories;
+use crate::centrality::{self, Scores};
+use crate::clusters::{self, NO_CLUSTER};
+use crate::components::{Components, ComponentsCell};
+use crate::similar::MinHash;
+
+/// A link graph held in memory with the titles of its articles. Article ids
+/// index `links` and `rev_title_map`; ids without an article have no links and
+/// an empty title.
+#[derive(Clone)]
+pub struct Graph {
+    pub name: String,
+    pub source: Source,
+    /// Starts at 1 and increases every time the graph is reloaded.
+    pub generation: u64,
+    /// When the data was taken from the database (UTC, RFC 3339). For a database
+    /// source that is when the graph was loaded, for a snapshot it is read from
+    /// its header.
+    pub taken_at: Option<String>,
+    pub links: LinkGraph,
+    pub title_map: HashMap<String, usize>,
+    pub rev_title_map: Vec<String>,
+    /// Old titles of articles moved by live updates, which are redirects to
+    /// them now. The crawl skips redirect pages, so no others are known.
+    pub redirects: HashMap<String, usize>,
+    /// The titles in `redirects` of every article, in the order they were added.
+    pub redirects_to: HashMap<usize, Vec<String>>,
+    /// Connection to the graph's database, `None` for snapshots.
+    pub conn: Option<PgPool>,
+    /// The last row of `graph_changes` applied to this graph.
+    pub change_seq: i64,
+    /// Centrality of every article, `None` until computed. Loaded from
+    /// `article_scores` for database sources, see [`Slot::compute_scores`].
+    pub scores: Option<Arc<Scores>>,
+    /// Categories of the articles, `None` for snapshots. Loaded with the graph,
+    /// live updates don't change them.
+    pub categories: Option<Arc<Categories>>,
+    /// Strongly connected components, computed on load and kept up to date by
+    /// live updates, see [`Slot::apply_changes`].
+    pub components: ComponentsCell,
+    /// MinHash index for `/similar`, built on load with `WIKILINKS_SIMILAR=1`
+    /// and empty otherwise.
+    pub minhash: Arc<MinHash>,
+}
+
+impl Graph {
+    pub async fn load(name: &str, source: Source) -> Result<Graph, Box<dyn Error + Send + Sync>> {
+        let (rows, conn, change_seq, taken_at, stored_scores, categories) = match &source {
+            Source::Database { url, wiki } => {
+                let conn = PgPoolOptions::new().connect(url).await?;
+
+                // read the position in graph_changes first. Changes made while the
+                // articles load are applied again later, which is harmless.
+                let position = query!(
+                    r#"SELECT coalesce(max(seq), 0) AS "seq!", to_char(now() AT TIME ZONE 'utc', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') AS "taken_at!"
+                       FROM graph_changes WHERE wiki = $1"#,
+                    wiki
+                )
+                    .fetch_one(&conn)
+                    .await?;
+
+                let rows = query!("SELECT id, title, condensed_links FROM articles WHERE wiki = $1 ORDER BY id ASC", wiki)
+                    .fetch_all(&conn)
+                    .await?
+                    .into_iter()
+                    .map(|a| (a.id as usize, a.title, a.condensed_links.into_iter().map(|v| v as usize).collect()))
+                    .collect::<Vec<_>>();
+
+                let scores = query!(
+                    r#"SELECT article_id, pagerank, betweenness, cluster, to_char(computed_at AT TIME ZONE 'utc', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') AS "computed_at!"
+                       FROM article_scores WHERE wiki = $1"#,
+                    wiki
+                )
+                    .fetch_all(&conn)
+                    .await?
+                    .into_iter()
+                    .map(|s| (s.article_id as usize, s.pagerank, s.betweenness, s.cluster, s.computed_at))
+                    .collect::<Vec<_>>();
+
+                let categories = Categories::load(&conn, wiki).await?;
+
+                (rows, Some(conn), position.seq, Some(position.taken_at), scores, Some(Arc::new(categories)))
+            }
+            Source::Snapshot(path) => {
+                let snapshot = load_snapshot(path)?;
+                (snapshot.rows, None, 0, snapshot.taken_at, vec![], None)
+            }
+        };
+
+        // ids come from a sequence shared by every wiki, so they are not
+        // contiguous within one wiki. Size the graph by the largest id.
+        let size = rows.iter().map(|r| r.0).max().unwrap_or(0) + 1;
+
+        let mut articles = vec![vec![]; size];
+        let mut title_map = HashMap::<String, usize>::with_capacity(rows.len());
+        let mut rev_title_map = vec![String::new(); size];
+
+        for (id, title, links) in rows {
+            articles[id] = links;
+            rev_title_map[id] = title.clone();
+            title_map.insert(title, id);
+        }
+
+        // links to ids without a row grow the graph
+        let links = LinkGraph::new(articles);
+        let size = links.len();
+        rev_title_map.resize(size, String::new());
+
+        // scores of articles that are gone since they were computed are dropped.
+        // Scores stored without clusters are computed again.
+        let complete = !stored_scores.is_empty() && stored_scores.iter().all(|s| s.3 >= 0);
+        let scores = complete.then(|| {
+            let mut scores = Scores {
+                pagerank: vec![0.0; size],
+                betweenness: vec![0.0; size],
+                cluster: vec![NO_CLUSTER; size],
+                cluster_sizes: vec![],
+                computed_at: None,
+            };
+            for (id, pagerank, betweenness, cluster, computed_at) in stored_scores.into_iter().filter(|s| s.0 < size) {
+                scores.pagerank[id] = pagerank;
+                scores.betweenness[id] = betweenness;
+                scores.cluster[id] = cluster as u32;
+                scores.computed_at = Some(computed_at);
+            }
+            scores.cluster_sizes = clusters::sizes(&scores.cluster);
+            Arc::new(scores)
+        });
+
+        let mut graph = Graph {
+            name: name.to_string(),
+            source,
+            generation: 1,
+            taken_at,
+            links,
+            title_map,
+            rev_title_map,
+            redirects: HashMap::new(),
+            redirects_to: HashMap::new(),
+            conn,
+            change_seq,
+            scores,
+            categories,
+            components: ComponentsCell::default(),
+            minhash: Arc::default(),
+        };
+        graph.components = ComponentsCell::new(Components::new(&graph));
+
+        if std::env::var("WIKILINKS_SIMILAR").as_deref() == Ok("1") {
+            graph.minhash = Arc::new(MinHash::new(&graph));
+        }
+
+        Ok(graph)
+    }
+
+    pub fn titles(&self, ids: &[usize]) -> Vec<String> {
+        ids.iter().map(|&id| self.rev_title_map[id].clone()).collect()
+    }
+
+    /// Apply rows of `graph_changes` and follow them in the components. Returns
+    /// false when the components went stale and have to be computed again.
+    fn update(&mut self, changes: Vec<Change>) -> bool {
+        // take the components out of their cell to change them in place
+        let mut components = self.components.replace(Arc::default());
+        let parts = Arc::make_mut(&mut components);
+
+        for change in changes {
+            let old_links = self.links.articles.get(change.id).cloned().unwrap_or_default();
+            let had_title = self.rev_title_map.get(change.id).is_some_and(|t| !t.is_empty());
+
+            self.apply_change(&change.kind, change.id, change.title, change.links);
+            if change.seq > self.change_seq { self.change_seq = change.seq; }
+
+            if !parts.stale && !parts.update(self, change.id, &old_links, had_title) {
+                parts.stale = true;
+            }
+        }
+
+        let current = !parts.stale;
+        self.components.replace(components);
+        current
+    }
+
+    /// Apply one row of `graph_changes`: an `upsert` sets the article's title and
+    /// links, a `delete` removes the article. Either way the article's old title
+    /// no longer maps to it.
+    fn apply_change(&mut self, kind: &str, id: usize, title: String, links: Vec<usize>) {
+        let old = self.rev_title_map.get_mut(id).map(std::mem::take).unwrap_or_default();
+        if self.title_map.get(&old) == Some(&id) {
+            self.title_map.remove(&old);
+        }
+
+        // new articles have ids past the end of the graph
+        let links = if kind == "delete" { vec![] } else { links };
+        self.links.set_links(id, links);
+        self.rev_title_map.resize(self.links.len(), String::new());
+
+        if kind == "delete" { return; }
+
+        if !old.is_empty() && old != title {
+            self.redirects.insert(old.clone(), id);
+            self.redirects_to.entry(id).or_default().push(old);
+        }
+        if let Some(target) = self.redirects.remove(&title) {
+            let titles = self.redirects_to.get_mut(&target).unwrap();
+            titles.retain(|t| *t != title);
+            if titles.is_empty() { self.redirects_to.remove(&target); }
+        }
+
+        self.rev_title_map[id] = title.clone();
+        self.title_map.insert(title, id);
+    }
+
+    /// Titles matching a SQL `LIKE` pattern (case-insensitive). A title equal to the
+    /// pattern without its `%` comes first, then the highest PageRank, then the
+    /// shortest. Searches the database when the graph has one, otherwise the
+    /// titles in memory.
+    pub async fn search(&self, pattern: &str, limit: usize) -> Result<Vec<String>, sqlx::Error> {
+        let (Some(conn), Source::Database { wiki, .. }) = (&self.conn, &self.source) else {
+            return Ok(self.search_titles(pattern, limit));
+        };
+
+        let exact = pattern.trim_matches('%').to_lowercase();
+
+        let titles = query!(
+            "SELECT a.title FROM articles a
+             LEFT JOIN article_scores s ON s.wiki = a.wiki AND s.article_id = a.id
+             WHERE a.wiki = $1 AND lower(a.title) LIKE $2
+             ORDER BY lower(a.title) = $3 DESC, s.pagerank DESC NULLS LAST, length(a.title) ASC, a.title ASC
+             LIMIT $4",
+            wiki,
+            pattern.to_lowercase(),
+            exact,
+            limit as i64
+        )
+            .fetch_all(conn)
+            .await?
+            .into_iter()
+            .map(|r| r.title)
+            .collect();
+
+        Ok(titles)
+    }
+
+    /// In-memory version of the database search, with the same ordering.
+    fn search_titles(&self, pattern: &str, limit: usize) -> Vec<String> {
+        let exact = pattern.trim_matches('%').to_lowercase();
+        let pagerank = |t: &str| self.scores.as_ref().map_or(0.0, |s| s.pagerank_of(self.title_map[t]));
+
+        let mut results = self.title_map
+            .keys()
+            .filter(|t| like(t, pattern))
+            .collect::<Vec<_>>();
+
+        results.sort_by(|a, b| {
+            (b.to_lowercase() == exact).cmp(&(a.to_lowercase() == exact))
+                .then_with(|| pagerank(b).total_cmp(&pagerank(a)))
+                .then_with(|| a.len().cmp(&b.len()))
+                .then_with(|| a.cmp(b))
+        });
+        results.into_iter().take(limit).cloned().collect()
+    }
+}
+
+/// SQL `LIKE` without escapes, ignoring case: `%` matches any run of
+/// characters and `_` any one. A mismatch only goes back to the last `%`, which
+/// takes one more character, so matching takes at most text × pattern steps.
+fn like(text: &str, pattern: &str) -> bool {
+    let same = |a: char, b: char| a == b || a.to_lowercase().eq(b.to_lowercase());
+
+    let (mut text, mut pattern) = (text, pattern);
+    // the pattern after the last `%` and the text it starts matching at
+    let mut star = None::<(&str, &str)>;
+
+    loop {
+        let (mut p, mut t) = (pattern.chars(), text.chars());
+
+        match (p.next(), t.next()) {
+            (Some('%'), _) => {
+                pattern = p.as_str();
+                star = Some((pattern, text));
+            }
+            (Some(pc), Some(tc)) if pc == '_' || same(pc, tc) => {
+                pattern = p.as_str();
+                text = t.as_str();
+            }
+            (None, None) => return true,
+            _ => {
+                let Some((after, from)) = star else { return false };
+                let mut from = from.chars();
+                if from.next().is_none() { return false; }
+
+                pattern = after;
+                text = from.as_str();
+                star = Some((after, text));
+            }
+        }
+    }
+}
+
+/// A row of `graph_changes`.
+struct Change {
+    seq: i64,
+    kind: String,
+    id: usize,
+    title: String,
+    links: Vec<usize>,
+}
+
+/// A named graph that can be reloaded while the server is running. Requests
+/// take the current [`Graph`] once and keep using it, so a reload never changes
+/// the graph under a search in progress; the old graph is dropped once the
+/// last request using it finishes.
+pub struct Slot {
+    graph: ArcSwap<Graph>,
+    /// Set while a reload, scores or change application updates the graph.
+    updating: AtomicBool,
+}
+
+impl Slot {
+    pub fn new(graph: Graph) -> Slot {
+        Slot { graph: ArcSwap::from_pointee(graph), updating: AtomicBool::new(false) }
+    }
+
+    pub fn current(&self) -> Arc<Graph> {
+        self.graph.load_full()
+    }
+
+    fn store(&self, graph: Graph) {
+        self.graph.store(Arc::new(graph));
+    }
+
+    /// Change a copy of the graph on a blocking thread, swap it in and increase
+    /// its generation, returning the result of `change` and the new generation.
+    /// Requests keep being served from the current graph meanwhile, and those in
+    /// progress keep it; the copy doubles the memory of the graph for as long
+    /// as the change takes. Only called while `updating` is set, which keeps
+    /// other changes out.
+    async fn modify<T: Send + 'static>(
+        &self,
+        change: impl FnOnce(&mut Graph) -> T + Send + 'static,
+    ) -> Result<(T, u64), tokio::task::JoinError> {
+        let current = self.current();
+
+        let (result, graph) = tokio::task::spawn_blocking(move || {
+            let mut graph = (*current).clone();
+            drop(current);
+
+            let result = change(&mut graph);
+            graph.generation += 1;
+            (result, graph)
+        }).await?;
+
+        let generation = graph.generation;
+        self.store(graph);
+        Ok((result, generation))
+    }
+
+    pub fn is_updating(&self) -> bool {
+        self.updating.load(Ordering::SeqCst)
+    }
+
+    /// Load the graph again from its source and swap it in, returning the new
+    /// generation. The current graph keeps serving requests while loading. A
+    /// graph that comes back without scores, like a snapshot, gets them
+    /// computed before the reload is done.
+    pub async fn reload(&self) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        if self.updating.swap(true, Ordering::SeqCst) {
+            return Err("the graph is already being updated".into());
+        }
+
+        let result = self.load_again().await;
+        self.updating.store(false, Ordering::SeqCst);
+        result
+    }
+
+    async fn load_again(&self) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        let current = self.current();
+        let mut graph = Graph::load(&current.name, current.source.clone()).await?;
+        graph.generation = current.generation + 1;
+        drop(current);
+
+        let (name, unscored) = (graph.name.clone(), graph.scores.is_none());
+        let generation = graph.generation;
+        self.store(graph);
+
+        if !unscored { return Ok(generation); }
+
+        // the reloaded graph is served meanwhile, without scores
+        match self.score(centrality::configured_samples()).await {
+            Ok(generation) => Ok(generation),
+            Err(e) => {
+                eprintln!("Failed to compute scores of graph {}: {}", name, e);
+                Ok(generation)
+            }
+        }
+    }
+
+    /// Compute the centrality scores and clusters of the current graph on a blocking thread and
+    /// swap in a copy of the graph that has them, like a change does. Scores of a
+    /// database graph are stored in `article_scores`, replacing the old ones.
+    /// Changes and reloads wait until the scores are done.
+    pub async fn compute_scores(&self, samples: usize) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        if self.updating.swap(true, Ordering::SeqCst) {
+            return Err("the graph is already being updated".into());
+        }
+
+        let result = self.score(samples).await;
+        self.updating.store(false, Ordering::SeqCst);
+        result
+    }
+
+    async fn score(&self, samples: usize) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        let current = self.current();
+
+        let graph = Arc::clone(&current);
+        let mut scores = tokio::task::spawn_blocking(move || centrality::compute(&graph, samples)).await?;
+
+        if let (Some(conn), Source::Database { wiki, .. }) = (&current.conn, &current.source) {
+            scores.computed_at = Some(store_scores(conn, wiki, &current, &scores).await?);
+        }
+        drop(current);
+
+        let scores = Arc::new(scores);
+        let ((), generation) = self.modify(|graph| graph.scores = Some(scores)).await?;
+
+        Ok(generation)
+    }
+
+    /// Apply the rows of `graph_changes` written since the graph was loaded or
+    /// last updated, see the `changes` scraper, returning the number of
+    /// changes. A copy of the graph is changed and swapped in, see `modify`.
+    /// Changes the components can't follow leave them stale until they are
+    /// computed again in the background. Snapshots never change.
+    pub async fn apply_changes(&self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        // a reload picks up the changes anyway
+        if self.updating.swap(true, Ordering::SeqCst) {
+            return Ok(0);
+        }
+
+        let result = self.apply_pending_changes().await;
+        self.updating.store(false, Ordering::SeqCst);
+        result
+    }
+
+    async fn apply_pending_changes(&self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let current = self.current();
+        let (Some(conn), Source::Database { wiki, .. }) = (&current.conn, &current.source) else {
+            return Ok(0);
+        };
+
+        let changes = query!(
+            "SELECT seq, kind, article_id, title, links FROM graph_changes WHERE wiki = $1 AND seq > $2 ORDER BY seq ASC",
+            wiki,
+            current.change_seq
+        )
+            .fetch_all(conn)
+            .await?
+            .into_iter()
+            .map(|c| Change {
+                seq: c.seq,
+                kind: c.kind,
+                id: c.article_id as usize,
+                title: c.title,
+                links: c.links.into_iter().map(|l| l as usize).collect(),
+            })
+            .collect::<Vec<_>>();
+
+        if changes.is_empty() { return Ok(0); }
+        let count = changes.len();
+
+        drop(current);
+        let (current_components, _) = self.modify(|graph| graph.update(changes)).await?;
+
+        if !current_components {
+            let graph = self.current();
+            let components = tokio::task::spawn_blocking({
+                let graph = Arc::clone(&graph);
+                move || Components::new(&graph)
+            }).await?;
+            graph.components.replace(Arc::new(components));
+        }
+
+        Ok(count)
+    }
+}
+
+/// Replace the stored scores of a wiki in one transaction, returning when they
+/// were computed.
+async fn store_scores(conn: &PgPool, wiki: &str, graph: &Graph, scores: &Scores) -> Result<String, sqlx::Error> {
+    let mut tx = conn.begin().await?;
+
+    query!("DELETE FROM article_scores WHERE wiki = $1", wiki)
+        .execute(&mut *tx)
+        .await?;
+
+    let ids = (0..graph.links.articles.len()).filter(|&id| !graph.rev_title_map[id].is_empty()).collect::<Vec<_>>();
+
+    for chunk in ids.chunks(100_000) {
+        let article_ids = chunk.iter().map(|&id| id as i32).collect::<Vec<_>>();
+        let pagerank = chunk.iter().map(|&id| scores.pagerank_of(id)).collect::<Vec<_>>();
+        let in_degree = chunk.iter().map(|&id| graph.links.backlinks[id].len() as i32).collect::<Vec<_>>();
+        let betweenness = chunk.iter().map(|&id| scores.betweenness_of(id)).collect::<Vec<_>>();
+        let cluster = chunk.iter().map(|&id| scores.cluster_of(id) as i32).collect::<Vec<_>>();
+
+        query!(
+            "INSERT INTO article_scores (wiki, article_id, pagerank, in_degree, betweenness, cluster)
+             SELECT $1, * FROM unnest($2::integer[], $3::double precision[], $4::integer[], $5::double precision[], $6::integer[])",
+            wiki,
+            &article_ids,
+            &pagerank,
+            &in_degree,
+            &betweenness,
+            &cluster
+        )
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // now() is the start of the transaction, the same for every row
+    let computed_at = query!(r#"SELECT to_char(now() AT TIME ZONE 'utc', 'YYYY-MM-DD"T"HH24:MI:SS"Z"') AS "computed_at!""#)
+        .fetch_one(&mut *tx)
+        .await?
+        .computed_at;
+
+    tx.commit().await?;
+
+    Ok(computed_at)
+}
+
+/// Every graph the server holds, by name. Routes without a `/g/{name}` prefix
+/// use the default graph, the first one configured.
+pub struct Registry {
+    slots: Vec<Slot>,
+}
+
+impl Registry {
+    pub fn new(graphs: Vec<Graph>) -> Registry {
+        assert!(!graphs.is_empty(), "at least one graph must be configured");
+        Registry { slots: graphs.into_iter().map(Slot::new).collect() }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Slot> {
+        self.slots.iter().find(|s| s.current().name == name)
+    }
+
+    pub fn default_slot(&self) -> &Slot {
+        &self.slots[0]
+    }
+
+    pub fn slots(&self) -> &[Slot] {
+        &self.slots
+    }
+}
+
+/// Parse the `name=source,name=source` list in `WIKILINKS_GRAPHS`. Without it the
+/// server serves a single graph of the `WIKILINKS_WIKI` wiki (default `enwiki`)
+/// from the default database, named after the wiki.
+pub fn configured_sources() -> Result<Vec<(String, Source)>, String> {
+    let Ok(graphs) = std::env::var("WIKILINKS_GRAPHS") else {
+        let wiki = std::env::var("WIKILINKS_WIKI").unwrap_or_else(|_| "enwiki".to_string());
+        let source = Source::parse(&format!("db:{}", wiki))?;
+        return Ok(vec![(wiki, source)]);
+    };
+
+    graphs
+        .split(',')
+        .filter(|g| !g.trim().is_empty())
+        .map(|g| {
+            let (name, source) = g.trim().split_once('=')
+                .ok_or_else(|| format!("expected name=source, got '{}'", g))?;
+            Ok((name.to_string(), Source::parse(source)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+impl Graph {
+    /// A snapshot-less graph of the given links whose articles are titled by
+    /// their ids, for the tests of the algorithms over it.
+    pub(crate) fn from_links(links: Vec<Vec<usize>>) -> Graph {
+        let links = LinkGraph::new(links);
+        let rev_title_map = (0..links.len()).map(|id| id.to_string()).collect::<Vec<_>>();
+
+        let graph = Graph {
+            name: "test".to_string(),
+            source: Source::Snapshot("test.tsv".into()),
+            generation: 1,
+            taken_at: None,
+            title_map: rev_title_map.iter().cloned().zip(0..).collect(),
+            rev_title_map,
+            links,
+            redirects: HashMap::new(),
+            redirects_to: HashMap::new(),
+            conn: None,
+            change_seq: 0,
+            scores: None,
+            categories: None,
+            components: ComponentsCell::default(),
+            minhash: Arc::default(),
+        };
+        graph.components.replace(Arc::new(Components::new(&graph)));
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moved_titles_become_redirects_until_taken() {
+        let mut graph = Graph::from_links(vec![vec![], vec![]]);
+
+        graph.apply_change("upsert", 0, "A".to_string(), vec![]);
+        graph.apply_change("upsert", 0, "B".to_string(), vec![]);
+        assert_eq!(graph.redirects, HashMap::from([("0".to_string(), 0), ("A".to_string(), 0)]));
+        assert_eq!(graph.redirects_to[&0], ["0", "A"]);
+
+        // 1 takes the old title A, which no longer redirects
+        graph.apply_change("upsert", 1, "A".to_string(), vec![]);
+        assert_eq!(graph.redirects.get("A"), None);
+        assert_eq!(graph.redirects_to[&0], ["0"]);
+        assert_eq!(graph.redirects_to[&1], ["1"]);
+
+        graph.apply_change("upsert", 0, "0".to_string(), vec![]);
+        assert_eq!(graph.redirects_to[&0], ["B"]);
+    }
+
+    #[test]
+    fn like_matches_wildcards() {
+        assert!(like("Albert Einstein", "albert%"));
+        assert!(like("Albert Einstein", "%EIN%"));
+        assert!(like("Albert Einstein", "Albert_Einstein"));
+        assert!(like("Albert Einstein", "%"));
+        assert!(like("", "%"));
+        assert!(like("", ""));
+        assert!(like("abc", "a%%c"));
+        assert!(like("abcbc", "%bc"));
+
+        assert!(!like("Albert Einstein", "einstein%"));
+        assert!(!like("Albert", "Albert_"));
+        assert!(!like("Albert", "Alber"));
+        assert!(!like("", "_"));
+        assert!(!like("abcb", "%bc"));
+    }
+
+    #[test]
+    fn like_matches_characters_not_bytes() {
+        assert!(like("Ærøskøbing", "_r_sk_bing"));
+        assert!(like("ÆRØ", "ærø"));
+        assert!(!like("Ø", "__"));
+    }
+
+    #[test]
+    fn like_gives_up_quickly_on_many_wildcards() {
+        let text = "a".repeat(10_000);
+        let pattern = format!("{}b", "%a".repeat(50));
+        assert!(!like(&text, &pattern));
+        assert!(like(&text, &"%a".repeat(50)));
+    }
+}
@@ -1,6 +1,13 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use bytes::Bytes;
 use http_body_util::combinators::BoxBody;
 use http_body_util::{BodyExt, Empty, Full};
+use hyper::body::{Body, Frame};
+use hyper::Request;
+use tokio::sync::mpsc;
+use crate::router::PathParams;
 
 pub fn empty() -> BoxBody<Bytes, hyper::Error> {
     Empty::new()
@@ -14,4 +21,32 @@ where T: Into<Bytes>,
     Full::new(chunk.into())
         .map_err(|never| match never {})
         .boxed()
-}
\ No newline at end of file
+}
+
+/// A body streamed from a channel, one chunk per message. It ends when every
+/// sender is dropped.
+pub fn channel(rx: mpsc::Receiver<Bytes>) -> BoxBody<Bytes, hyper::Error> {
+    ChannelBody(rx).boxed()
+}
+
+struct ChannelBody(mpsc::Receiver<Bytes>);
+
+impl Body for ChannelBody {
+    type Data = Bytes;
+    type Error = hyper::Error;
+
+    fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, hyper::Error>>> {
+        self.get_mut().0.poll_recv(cx).map(|chunk| chunk.map(|c| Ok(Frame::data(c))))
+    }
+}
+
+/// The decoded query string parameters of a request. Later values win.
+pub fn query_params<B>(req: &Request<B>) -> HashMap<String, String> {
+    let query = req.uri().query().unwrap_or("");
+    form_urlencoded::parse(query.as_bytes()).into_owned().collect()
+}
+
+/// A `{name}` segment of the route the request matched, see [`crate::router::ROUTES`].
+pub fn path_param<'a, B>(req: &'a Request<B>, name: &str) -> Option<&'a str> {
+    req.extensions().get::<PathParams>()?.0.get(name).map(String::as_str)
+}
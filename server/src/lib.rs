@@ -0,0 +1,22 @@
+//! The graph, searches and HTTP service behind the `wikilinks-server` and
+//! `wikilinks` binaries.
+
+pub mod article;
+pub mod batch;
+pub mod categories;
+pub mod centrality;
+pub mod clusters;
+pub mod compare;
+pub mod components;
+pub mod connections;
+pub mod cors;
+pub mod graph;
+pub mod helpers;
+pub mod neighborhood;
+pub mod router;
+pub mod service;
+pub mod similar;
+pub mod suggest;
+pub mod targets;
+pub mod tls;
+pub mod tree;
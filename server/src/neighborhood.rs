@@ -0,0 +1,191 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use serde::Serialize;
+use crate::graph::Graph;
+
+/// Most articles a neighbourhood can be asked for.
+pub const MAX_LIMIT: usize = 1000;
+/// Most hops a neighbourhood can be asked for.
+pub const MAX_HOPS: usize = 3;
+
+/// An induced subgraph for drawing: some articles and every link between them.
+#[derive(Serialize)]
+pub struct Neighborhood {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    /// Set when articles were left out to stay within the limit.
+    pub truncated: bool,
+}
+
+#[derive(Serialize)]
+pub struct Node {
+    pub id: usize,
+    pub title: String,
+    pub in_degree: usize,
+    pub out_degree: usize,
+    /// Whether the article is on a shortest path between the two titles.
+    pub on_path: bool,
+}
+
+#[derive(Serialize)]
+pub struct Edge {
+    pub source: usize,
+    pub target: usize,
+}
+
+/// The articles within `hops` links of `center` in either direction, at most
+/// `limit` of them. Each hop keeps its most linked articles first, and only
+/// those are expanded further. With `to`, every article on a shortest path from
+/// `center` to `to` is included too.
+pub fn neighborhood(graph: &Graph, center: usize, hops: usize, limit: usize, to: Option<usize>) -> Neighborhood {
+    let mut selected = HashMap::<usize, bool>::new();
+    let mut truncated = false;
+
+    if let Some(to) = to {
+        let mut on_path = shortest_path_nodes(graph, center, to);
+
+        // the endpoints always stay, the rest by degree
+        on_path.sort_by_key(|&id| (id != center && id != to, std::cmp::Reverse(graph.links.degree(id))));
+        truncated |= on_path.len() > limit.max(2);
+        on_path.truncate(limit.max(2));
+
+        selected.extend(on_path.into_iter().map(|id| (id, true)));
+    }
+
+    selected.entry(center).or_insert(false);
+
+    let mut frontier = vec![center];
+    for _ in 0..hops {
+        if frontier.is_empty() { break; }
+
+        let mut next = frontier
+            .iter()
+            .flat_map(|&id| graph.links.articles[id].iter().chain(&graph.links.backlinks[id]))
+            .copied()
+            .filter(|id| !selected.contains_key(id))
+            .collect::<Vec<_>>();
+
+        next.sort_unstable();
+        next.dedup();
+        next.sort_by_key(|&id| std::cmp::Reverse(graph.links.degree(id)));
+
+        let room = limit.saturating_sub(selected.len());
+        truncated |= next.len() > room;
+        next.truncate(room);
+
+        selected.extend(next.iter().map(|&id| (id, false)));
+        frontier = next;
+    }
+
+    let mut nodes = selected
+        .iter()
+        .map(|(&id, &on_path)| Node {
+            id,
+            title: graph.rev_title_map[id].clone(),
+            in_degree: graph.links.backlinks[id].len(),
+            out_degree: graph.links.articles[id].len(),
+            on_path,
+        })
+        .collect::<Vec<_>>();
+    nodes.sort_by_key(|n| n.id);
+
+    let edges = nodes
+        .iter()
+        .flat_map(|n| graph.links.articles[n.id].iter().map(move |&target| (n.id, target)))
+        .filter(|(_, target)| selected.contains_key(target))
+        .map(|(source, target)| Edge { source, target })
+        .collect();
+
+    Neighborhood { nodes, edges, truncated }
+}
+
+/// Every article on some shortest path from `start` to `end`, by id: the ones
+/// whose distance from `start` plus distance to `end` is the length of the path.
+/// Only searches as far as `end`, and back from it only through articles
+/// closer to `start`.
+fn shortest_path_nodes(graph: &Graph, start: usize, end: usize) -> Vec<usize> {
+    if !graph.components.get().reachable(start, end) { return vec![]; }
+
+    let from_start = distances(&graph.links.articles, start, end);
+    let Some(&length) = from_start.get(&end) else { return vec![] };
+
+    let mut on_path = HashSet::from([end]);
+    let mut level = vec![end];
+    for distance in (0..length).rev() {
+        level = level
+            .iter()
+            .flat_map(|&id| &graph.links.backlinks[id])
+            .copied()
+            .filter(|prev| from_start.get(prev) == Some(&distance))
+            .filter(|&prev| on_path.insert(prev))
+            .collect();
+    }
+
+    let mut on_path = on_path.into_iter().collect::<Vec<_>>();
+    on_path.sort_unstable();
+    on_path
+}
+
+/// Breadth-first distances from `start` along `links`, until `end` is found.
+/// Every article closer than `end` has its distance by then.
+fn distances(links: &[Vec<usize>], start: usize, end: usize) -> HashMap<usize, usize> {
+    let mut distance = HashMap::from([(start, 0)]);
+    let mut queue = VecDeque::from([start]);
+    if start == end { return distance; }
+
+    while let Some(curr) = queue.pop_front() {
+        let d = distance[&curr];
+
+        for &link in &links[curr] {
+            if distance.contains_key(&link) { continue; }
+
+            distance.insert(link, d + 1);
+            if link == end { return distance; }
+            queue.push_back(link);
+        }
+    }
+
+    distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(neighborhood: &Neighborhood) -> Vec<(usize, bool)> {
+        neighborhood.nodes.iter().map(|n| (n.id, n.on_path)).collect()
+    }
+
+    #[test]
+    fn includes_every_shortest_path() {
+        // two shortest paths from 0 to 3, through 1 and 2, and a longer one
+        // through 4 and 5. 6 is far away from all of them.
+        let graph = Graph::from_links(vec![vec![1, 2, 4], vec![3], vec![3], vec![], vec![5], vec![3], vec![]]);
+
+        let around = neighborhood(&graph, 0, 0, 10, Some(3));
+        assert_eq!(ids(&around), vec![(0, true), (1, true), (2, true), (3, true)]);
+        assert_eq!(around.edges.len(), 4);
+        assert!(!around.truncated);
+
+        assert_eq!(shortest_path_nodes(&graph, 0, 0), vec![0]);
+        assert!(shortest_path_nodes(&graph, 3, 0).is_empty());
+        assert!(shortest_path_nodes(&graph, 0, 6).is_empty());
+    }
+
+    #[test]
+    fn stops_at_the_hop_and_article_limits() {
+        // a chain 0 - 1 - 2 - 3 - 4, 1 also linked from 5
+        let graph = Graph::from_links(vec![vec![1], vec![2], vec![3], vec![4], vec![], vec![1]]);
+
+        let around = neighborhood(&graph, 2, 1, 10, None);
+        assert_eq!(ids(&around), vec![(1, false), (2, false), (3, false)]);
+        assert!(!around.truncated);
+
+        let around = neighborhood(&graph, 2, 2, 10, None);
+        assert_eq!(ids(&around).len(), 6);
+
+        // 1 has the most links and stays
+        let around = neighborhood(&graph, 2, 2, 2, None);
+        assert_eq!(ids(&around), vec![(1, false), (2, false)]);
+        assert!(around.truncated);
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use hyper::Method;
+use percent_encoding::percent_decode_str;
+
+/// Every route of [`crate::service`], as method and path pattern. A `{name}`
+/// segment matches any one non-empty path segment, percent-decoded, so a `/`
+/// inside a title has to be sent as `%2F`. Keep in step with the handlers in
+/// `service.rs`.
+pub const ROUTES: &[(Method, &str)] = &[
+    (Method::GET, "/"),
+    (Method::GET, "/graphs"),
+    (Method::GET, "/article/{title}"),
+    (Method::POST, "/admin/reload"),
+    (Method::POST, "/admin/scores"),
+    (Method::POST, "/bfs"),
+    (Method::POST, "/dfs"),
+    (Method::POST, "/batch"),
+    (Method::GET, "/tree"),
+    (Method::POST, "/nearest"),
+    (Method::POST, "/search"),
+    (Method::POST, "/compare"),
+    (Method::GET, "/neighborhood"),
+    (Method::GET, "/reachable"),
+    (Method::GET, "/component"),
+    (Method::GET, "/components"),
+    (Method::GET, "/scores"),
+    (Method::GET, "/top"),
+    (Method::GET, "/cluster"),
+    (Method::GET, "/clusters"),
+    (Method::POST, "/clusters"),
+    (Method::GET, "/category-path"),
+    (Method::POST, "/categories"),
+    (Method::GET, "/similar"),
+    (Method::GET, "/suggest"),
+];
+
+/// The values of the `{name}` segments of the matched route, stored in the
+/// request's extensions. See [`crate::helpers::path_param`].
+#[derive(Clone, Debug, Default)]
+pub struct PathParams(pub HashMap<String, String>);
+
+pub enum Resolved {
+    Route { pattern: &'static str, params: PathParams },
+    /// The path has routes, but not for this method. Lists the methods it has.
+    WrongMethod(Vec<Method>),
+    NotFound,
+}
+
+/// Find the route for a request.
+pub fn resolve(method: &Method, path: &str) -> Resolved {
+    let mut allowed = vec![];
+
+    for (route_method, pattern) in ROUTES {
+        let Some(params) = matches(pattern, path) else { continue };
+
+        if route_method == method {
+            return Resolved::Route { pattern, params: PathParams(params) };
+        }
+        allowed.push(route_method.clone());
+    }
+
+    match allowed.is_empty() {
+        true => Resolved::NotFound,
+        false => Resolved::WrongMethod(allowed),
+    }
+}
+
+/// The `Allow` header for a path with routes for `methods`. Every path also
+/// answers `OPTIONS`, and `HEAD` wherever it answers `GET`.
+pub fn allow(methods: &[Method]) -> String {
+    let mut allow = methods.iter().map(Method::as_str).collect::<Vec<_>>();
+    if methods.contains(&Method::GET) {
+        allow.push("HEAD");
+    }
+    allow.push("OPTIONS");
+    allow.join(", ")
+}
+
+fn matches(pattern: &str, path: &str) -> Option<HashMap<String, String>> {
+    let mut params = HashMap::new();
+    let (mut pattern, mut path) = (pattern.split('/'), path.split('/'));
+
+    loop {
+        match (pattern.next(), path.next()) {
+            (None, None) => return Some(params),
+            (Some(expected), Some(segment)) => {
+                match expected.strip_prefix('{').and_then(|p| p.strip_suffix('}')) {
+                    Some(_) if segment.is_empty() => return None,
+                    Some(name) => {
+                        params.insert(name.to_string(), percent_decode_str(segment).decode_utf8_lossy().into_owned());
+                    }
+                    None if expected != segment => return None,
+                    None => {}
+                }
+            }
+            _ => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The pattern and parameters of the route for a request, panicking when
+    /// there is none.
+    fn route(method: Method, path: &str) -> (&'static str, HashMap<String, String>) {
+        match resolve(&method, path) {
+            Resolved::Route { pattern, params } => (pattern, params.0),
+            Resolved::WrongMethod(allowed) => panic!("{} {} only allows {:?}", method, path, allowed),
+            Resolved::NotFound => panic!("{} {} not found", method, path),
+        }
+    }
+
+    fn allowed(method: Method, path: &str) -> Option<Vec<Method>> {
+        match resolve(&method, path) {
+            Resolved::WrongMethod(allowed) => Some(allowed),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn resolves_fixed_paths() {
+        assert_eq!(route(Method::GET, "/"), ("/", HashMap::new()));
+        assert_eq!(route(Method::POST, "/bfs").0, "/bfs");
+        assert_eq!(route(Method::GET, "/clusters").0, "/clusters");
+        assert_eq!(route(Method::POST, "/clusters").0, "/clusters");
+
+        assert!(matches!(resolve(&Method::GET, "/nowhere"), Resolved::NotFound));
+        assert!(matches!(resolve(&Method::GET, "/bfs/more"), Resolved::NotFound));
+    }
+
+    #[test]
+    fn decodes_path_params() {
+        let title = |path| route(Method::GET, path).1["title"].clone();
+
+        assert_eq!(title("/article/Physics"), "Physics");
+        assert_eq!(title("/article/Albert%20Einstein"), "Albert Einstein");
+        assert_eq!(title("/article/AC%2FDC"), "AC/DC");
+        assert_eq!(title("/article/Caf%C3%A9"), "Café");
+        assert_eq!(title("/article/100%25"), "100%");
+    }
+
+    #[test]
+    fn params_match_exactly_one_non_empty_segment() {
+        assert!(matches!(resolve(&Method::GET, "/article/"), Resolved::NotFound));
+        assert!(matches!(resolve(&Method::GET, "/article"), Resolved::NotFound));
+        assert!(matches!(resolve(&Method::GET, "/article/AC/DC"), Resolved::NotFound));
+    }
+
+    #[test]
+    fn lists_the_methods_of_a_path_for_any_other() {
+        assert_eq!(allowed(Method::GET, "/bfs"), Some(vec![Method::POST]));
+        assert_eq!(allowed(Method::DELETE, "/clusters"), Some(vec![Method::GET, Method::POST]));
+        assert_eq!(allowed(Method::POST, "/article/Physics"), Some(vec![Method::GET]));
+
+        // the service answers OPTIONS itself and turns HEAD into GET, neither
+        // has routes of its own
+        assert_eq!(allowed(Method::OPTIONS, "/bfs"), Some(vec![Method::POST]));
+        assert_eq!(allowed(Method::HEAD, "/tree"), Some(vec![Method::GET]));
+        assert!(allowed(Method::OPTIONS, "/nowhere").is_none());
+    }
+
+    #[test]
+    fn allow_adds_head_and_options() {
+        assert_eq!(allow(&[Method::POST]), "POST, OPTIONS");
+        assert_eq!(allow(&[Method::GET]), "GET, HEAD, OPTIONS");
+        assert_eq!(allow(&[Method::GET, Method::POST]), "GET, POST, HEAD, OPTIONS");
+    }
+}
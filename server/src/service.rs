@@ -1,20 +1,367 @@
 use http_body_util::BodyExt;
-use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use http_body_util::combinators::BoxBody;
 use hyper::body::{Bytes, Incoming};
 use hyper::{header, Method, Request, Response};
+use hyper::header::HeaderValue;
 use hyper::http::StatusCode;
-use sqlx::{query, PgConnection, PgPool};
-use crate::helpers::{empty, full};
+use crate::article;
+use crate::batch;
+use crate::categories;
+use crate::centrality::{self, Ranking};
+use crate::clusters;
+use crate::compare;
+use crate::cors;
+use crate::graph::{Graph, Registry, Slot};
+use crate::helpers::{channel, empty, full, path_param, query_params};
+use crate::neighborhood::{self, MAX_HOPS, MAX_LIMIT};
+use crate::router::{self, Resolved};
+use crate::suggest;
+use crate::targets::{NearestQuery, TargetError};
+use crate::tree;
+use wikilinks_core::{BreadthFirst, DepthFirst, Search};
+
+/// Sent with every response, the generation of the graph that answered it.
+pub const GENERATION_HEADER: &str = "x-graph-generation";
+
+pub async fn service(req: Request<Incoming>, registry: Arc<Registry>) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
+    let origin = req.headers().get(header::ORIGIN).cloned();
+
+    let mut resp = dispatch(req, registry).await?;
+    cors::apply(resp.headers_mut(), origin.as_ref());
+
+    Ok(resp)
+}
+
+/// Find the graph and the route for a request and answer it. `HEAD` is
+/// answered like `GET` without the body, `OPTIONS` with the allowed methods.
+async fn dispatch(mut req: Request<Incoming>, registry: Arc<Registry>) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
+    // /g/{name}/... routes to a named graph, everything else to the default one
+    let (slot, path) = match req.uri().path().strip_prefix("/g/") {
+        Some(rest) => {
+            let (name, path) = rest.split_once('/').unwrap_or((rest, ""));
+
+            let Some(slot) = registry.get(name) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            (slot, format!("/{}", path))
+        }
+        None => (registry.default_slot(), req.uri().path().to_string()),
+    };
+
+    let head = req.method() == Method::HEAD;
+    if head {
+        *req.method_mut() = Method::GET;
+    }
+
+    let pattern = match router::resolve(req.method(), &path) {
+        Resolved::Route { pattern, params } => {
+            req.extensions_mut().insert(params);
+            pattern
+        }
+        Resolved::WrongMethod(allowed) if req.method() == Method::OPTIONS => {
+            // a CORS preflight, or a client asking what the path allows
+            let headers = req.headers()
+                .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+                .cloned()
+                .unwrap_or(HeaderValue::from_static("authorization, content-type"));
+
+            return Ok(Response::builder()
+                .status(StatusCode::NO_CONTENT)
+                .header(header::ALLOW, router::allow(&allowed))
+                .header(header::ACCESS_CONTROL_ALLOW_METHODS, router::allow(&allowed))
+                .header(header::ACCESS_CONTROL_ALLOW_HEADERS, headers)
+                .header(header::ACCESS_CONTROL_MAX_AGE, "86400")
+                .body(empty())
+                .unwrap());
+        }
+        Resolved::WrongMethod(allowed) => {
+            return Ok(Response::builder()
+                .status(StatusCode::METHOD_NOT_ALLOWED)
+                .header(header::ALLOW, router::allow(&allowed))
+                .body(empty())
+                .unwrap());
+        }
+        Resolved::NotFound => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(empty())
+                .unwrap());
+        }
+    };
+
+    // hold on to this graph for the whole request, a reload swaps in a new one
+    let graph = slot.current();
+
+    let mut resp = route(req, &registry, slot, &graph, pattern).await?;
+    resp.headers_mut().insert(GENERATION_HEADER, HeaderValue::from(graph.generation));
+    resp.headers_mut().insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, HeaderValue::from_static(GENERATION_HEADER));
+
+    if head {
+        *resp.body_mut() = empty();
+    }
+
+    Ok(resp)
+}
+
+async fn route(req: Request<Incoming>, registry: &Arc<Registry>, slot: &Slot, graph: &Arc<Graph>, pattern: &str) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
+    let Graph { links, title_map, rev_title_map, .. } = &**graph;
+
+    match (req.method(), pattern) {
+        (&Method::GET, "/graphs") => {
+            // one graph per line: name, article count, source, generation and
+            // when it was taken, tab separated
+            let graphs = registry.slots()
+                .iter()
+                .map(|s| s.current())
+                .map(|g| format!("{}\t{}\t{}\t{}\t{}", g.name, g.title_map.len(), g.source, g.generation, g.taken_at.as_deref().unwrap_or("")))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .body(full(graphs))
+                .unwrap())
+        }
+
+        (&Method::GET, "/article/{title}") => {
+            // [?offset=0][&limit=100] page through the links and backlinks
+            let params = query_params(&req);
+            let offset = params.get("offset").and_then(|o| o.parse().ok()).unwrap_or(0);
+            let limit = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(100).min(MAX_LIMIT);
+
+            let Some((id, redirected_from)) = path_param(&req, "title").and_then(|t| article::resolve(graph, t)) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            let detail = article::detail(graph, id, redirected_from, offset, limit);
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(serde_json::to_string(&detail).unwrap()))
+                .unwrap())
+        }
+
+        (&Method::POST, "/admin/reload") => {
+            if !authorized(&req) {
+                return Ok(Response::builder()
+                    .status(StatusCode::FORBIDDEN)
+                    .body(empty())
+                    .unwrap());
+            }
+
+            if slot.is_updating() {
+                return Ok(Response::builder()
+                    .status(StatusCode::CONFLICT)
+                    .body(full("graph is already being updated"))
+                    .unwrap());
+            }
+
+            // build the new graph in the background, this request returns right away
+            let registry = Arc::clone(registry);
+            let name = graph.name.clone();
+            tokio::spawn(async move {
+                let slot = registry.get(&name).unwrap();
+                match slot.reload().await {
+                    Ok(generation) => println!("Reloaded graph {}, generation {}", name, generation),
+                    Err(e) => eprintln!("Failed to reload graph {}: {}", name, e),
+                }
+            });
+
+            Ok(Response::builder()
+                .status(StatusCode::ACCEPTED)
+                .body(full(format!("reloading {} from generation {}", graph.name, graph.generation)))
+                .unwrap())
+        }
+
+        (&Method::POST, "/admin/scores") => {
+            if !authorized(&req) {
+                return Ok(Response::builder()
+                    .status(StatusCode::FORBIDDEN)
+                    .body(empty())
+                    .unwrap());
+            }
+
+            if slot.is_updating() {
+                return Ok(Response::builder()
+                    .status(StatusCode::CONFLICT)
+                    .body(full("graph is already being updated"))
+                    .unwrap());
+            }
+
+            let registry = Arc::clone(registry);
+            let name = graph.name.clone();
+            tokio::spawn(async move {
+                let slot = registry.get(&name).unwrap();
+                match slot.compute_scores(centrality::configured_samples()).await {
+                    Ok(generation) => println!("Computed scores of graph {}, generation {}", name, generation),
+                    Err(e) => eprintln!("Failed to compute scores of graph {}: {}", name, e),
+                }
+            });
+
+            Ok(Response::builder()
+                .status(StatusCode::ACCEPTED)
+                .body(full(format!("computing scores of {} from generation {}", graph.name, graph.generation)))
+                .unwrap())
+        }
+
+        (&Method::GET, "/scores") => {
+            // ?title=X
+            let params = query_params(&req);
+
+            let Some(scores) = &graph.scores else {
+                return Ok(Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .body(full("scores are not computed yet"))
+                    .unwrap());
+            };
+
+            let Some(&id) = params.get("title").and_then(|t| title_map.get(t)) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(serde_json::to_string(&scores.article(graph, id)).unwrap()))
+                .unwrap())
+        }
+
+        (&Method::GET, "/top") => {
+            // ?by=pagerank|in_degree|betweenness[&limit=20]
+            let params = query_params(&req);
+            let limit = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(20).min(MAX_LIMIT);
+
+            let Some(scores) = &graph.scores else {
+                return Ok(Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .body(full("scores are not computed yet"))
+                    .unwrap());
+            };
+
+            let Some(ranking) = Ranking::parse(params.get("by").map(String::as_str).unwrap_or("pagerank")) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(full("expected by=pagerank, in_degree or betweenness"))
+                    .unwrap());
+            };
+
+            let top = scores.top(graph, ranking, limit);
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(serde_json::to_string(&top).unwrap()))
+                .unwrap())
+        }
+
+        (&Method::GET, "/cluster") => {
+            // ?id=N or ?title=X, [&limit=20]
+            let params = query_params(&req);
+            let limit = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(20).min(MAX_LIMIT);
+
+            let Some(scores) = &graph.scores else {
+                return Ok(Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .body(full("clusters are not computed yet"))
+                    .unwrap());
+            };
+
+            let cluster = match (params.get("id"), params.get("title")) {
+                (Some(id), _) => id.parse::<u32>().ok().filter(|&c| (c as usize) < scores.cluster_sizes.len()),
+                (None, Some(title)) => title_map.get(title).map(|&id| scores.cluster_of(id)).filter(|&c| c != clusters::NO_CLUSTER),
+                (None, None) => None,
+            };
+
+            let Some(cluster) = cluster else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            let info = clusters::info(graph, scores, cluster, limit);
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(serde_json::to_string(&info).unwrap()))
+                .unwrap())
+        }
+
+        (&Method::GET, "/clusters") => {
+            // [?limit=20], the largest clusters
+            let params = query_params(&req);
+            let limit = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(20).min(MAX_LIMIT);
+
+            let Some(scores) = &graph.scores else {
+                return Ok(Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .body(full("clusters are not computed yet"))
+                    .unwrap());
+            };
+
+            // clusters are numbered by size
+            let largest = scores.cluster_sizes
+                .iter()
+                .take(limit)
+                .enumerate()
+                .map(|(cluster, &size)| serde_json::json!({ "cluster": cluster, "size": size }))
+                .collect::<Vec<_>>();
+
+            let clusters = serde_json::json!({ "count": scores.cluster_sizes.len(), "largest": largest });
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(clusters.to_string()))
+                .unwrap())
+        }
+
+        (&Method::POST, "/clusters") => {
+            // titles one per line, e.g. a path; answers title and cluster, tab separated.
+            // Unknown titles and graphs without clusters get an empty cluster.
+            let body = req.collect().await?.to_bytes();
+            let body = String::from_utf8_lossy(body.as_ref()).to_string();
+
+            let lines = body
+                .split("\n")
+                .filter(|t| !t.is_empty())
+                .map(|title| {
+                    let cluster = title_map.get(title)
+                        .zip(graph.scores.as_ref())
+                        .map(|(&id, scores)| scores.cluster_of(id))
+                        .filter(|&c| c != clusters::NO_CLUSTER);
+
+                    match cluster {
+                        Some(cluster) => format!("{}\t{}", title, cluster),
+                        None => format!("{}\t", title),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .body(full(lines))
+                .unwrap())
+        }
 
-pub async fn service(req: Request<Incoming>, articles: Arc<Vec<Vec<usize>>>, title_map: Arc<HashMap<String, usize>>, conn: Arc<PgPool>, rev_title_map: Arc<Vec<String>>) -> Result<Response<BoxBody<Bytes, hyper::Error>>, hyper::Error> {
-    match (req.method(), req.uri().path()) {
         (&Method::GET, "/") => {
             let resp = Response::builder()
                 .status(StatusCode::OK)
-                .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-                .body(full(format!("{}", articles.len())))
+                .body(full(format!("{}", links.len())))
                 .unwrap();
 
             Ok(resp)
@@ -26,66 +373,347 @@ pub async fn service(req: Request<Incoming>, articles: Arc<Vec<Vec<usize>>>, tit
             let endpoints = body.split("\n").collect::<Vec<_>>();
             let (start, end) = (endpoints[0].to_string(), endpoints[1].to_string());
 
-            dbg!(&start, &end);
-
             let (Some(&start), Some(&end)) = (title_map.get(&start), title_map.get(&end)) else {
                 return Ok(Response::builder()
-                    .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
                     .status(StatusCode::NOT_FOUND)
                     .body(empty())
                     .unwrap());
             };
 
-            let mut queue = VecDeque::<usize>::with_capacity(articles.len());
-            let mut visited = vec![false; articles.len()];
-            let mut visit_count = 0;
-            //let mut visited = HashSet::<i32>::with_capacity(articles.len());
-            let mut predecessor = vec![0usize; articles.len()];
-            //let mut predecessor = HashMap::<usize, usize>::with_capacity(articles.len());
-
-            queue.push_back(start);
-            visited[start] = true;
+            // unreachable pairs are known without searching
+            let path = match graph.components.reachable(start, end).await {
+                true => BreadthFirst.path(&graph.links, start, end),
+                false => None,
+            };
 
-            while !queue.is_empty() {
-                let curr = queue.pop_front().unwrap();
+            let Some(path) = path else {
+                return Ok(Response::builder()
+                    .status(StatusCode::IM_A_TEAPOT)
+                    .body(empty())
+                    .unwrap());
+            };
 
-                if curr == end {
-                    let mut curr = end;
-                    let mut path = vec![rev_title_map[curr].clone()];
-                    while curr != start {
-                        curr = predecessor[curr];
-                        path.push(rev_title_map[curr].clone());
-                    }
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .body(full(graph.titles(&path).join("\n")))
+                .unwrap())
+        }
 
-                    path.reverse();
+        (&Method::POST, "/batch") => {
+            // (start, end) pairs as JSON or NDJSON, see batch::parse. Answers are
+            // streamed back as NDJSON while the searches run.
+            let body = req.collect().await?.to_bytes();
+            let body = String::from_utf8_lossy(body.as_ref()).to_string();
 
+            let pairs = match batch::parse(&body) {
+                Ok(pairs) => pairs,
+                Err(e) => {
                     return Ok(Response::builder()
-                        .status(StatusCode::OK)
-                        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-                        .body(full(path.join("\n")))
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(full(format!("expected pairs as {{\"from\": ..., \"to\": ...}} or [from, to]: {}", e)))
                         .unwrap());
                 }
+            };
+
+            if pairs.len() > batch::MAX_PAIRS {
+                return Ok(Response::builder()
+                    .status(StatusCode::PAYLOAD_TOO_LARGE)
+                    .body(full(format!("at most {} pairs per batch", batch::MAX_PAIRS)))
+                    .unwrap());
+            }
+
+            let (tx, rx) = tokio::sync::mpsc::channel(64);
+            let graph = Arc::clone(graph);
+            tokio::task::spawn_blocking(move || batch::run(&graph, &pairs, tx));
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/x-ndjson")
+                .body(channel(rx))
+                .unwrap())
+        }
+
+        (&Method::GET, "/tree") => {
+            // ?from=A[&format=ndjson|binary][&max_distance=N], every article
+            // reachable from A with its distance and parent, see tree::Format
+            let params = query_params(&req);
+            let max_distance = params.get("max_distance").and_then(|d| d.parse().ok()).unwrap_or(u32::MAX);
+
+            let Some(format) = tree::Format::parse(params.get("format").map(String::as_str).unwrap_or("ndjson")) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(full("expected format=ndjson or binary"))
+                    .unwrap());
+            };
+
+            let Some(&start) = params.get("from").and_then(|t| title_map.get(t)) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            let (tx, rx) = tokio::sync::mpsc::channel(16);
+            let graph = Arc::clone(graph);
+            tokio::task::spawn_blocking(move || tree::stream(&graph, start, format, max_distance, tx));
+
+            let content_type = match format {
+                tree::Format::Ndjson => "application/x-ndjson",
+                tree::Format::Binary => "application/octet-stream",
+            };
 
-                let adj = &articles[curr];
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, content_type)
+                .body(channel(rx))
+                .unwrap())
+        }
 
-                for &link in adj {
-                    if visited[link] { continue; }
+        (&Method::POST, "/nearest") => {
+            // a JSON start title and target set, see targets::NearestQuery
+            let body = req.collect().await?.to_bytes();
 
-                    predecessor[link] = curr;
-                    visited[link] = true;
-                    queue.push_back(link);
-                    visit_count += 1;
+            let query = match serde_json::from_slice::<NearestQuery>(&body) {
+                Ok(query) => query,
+                Err(e) => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::BAD_REQUEST)
+                        .body(full(format!("expected from and one of titles, prefix or category: {}", e)))
+                        .unwrap());
+                }
+            };
+
+            let targets = match query.resolve(graph) {
+                Ok(targets) => targets,
+                Err(TargetError::NoCategories) => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::SERVICE_UNAVAILABLE)
+                        .body(full("this graph has no categories"))
+                        .unwrap());
                 }
+                Err(TargetError::UnknownCategory) => {
+                    return Ok(Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(full("unknown category"))
+                        .unwrap());
+                }
+            };
+
+            let count = targets.iter().filter(|&&t| t).count();
+
+            let (Some(&from), true) = (title_map.get(&query.from), count > 0) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            let Some(path) = BreadthFirst.nearest(&graph.links, from, |id| targets[id]) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::IM_A_TEAPOT)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            let nearest = serde_json::json!({
+                "target": rev_title_map[*path.last().unwrap()],
+                "distance": path.len() - 1,
+                "path": graph.titles(&path),
+                "targets": count,
+            });
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(nearest.to_string()))
+                .unwrap())
+        }
+
+        (&Method::GET, "/category-path") => {
+            // ?from=A&category=C[&depth=1], the shortest path from A to any article
+            // in C or a subcategory at most `depth` levels below it
+            let params = query_params(&req);
+            let depth = params.get("depth").and_then(|d| d.parse().ok()).unwrap_or(1).min(categories::MAX_DEPTH);
+
+            let Some(categories) = &graph.categories else {
+                return Ok(Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .body(full("this graph has no categories"))
+                    .unwrap());
+            };
+
+            let from = params.get("from").and_then(|t| title_map.get(t));
+            let category = params.get("category").and_then(|c| categories.find(c));
 
-                if visit_count % 10 == 0 { println!("{}", visit_count); }
+            let (Some(&from), Some(category)) = (from, category) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            let within = categories.within(&[category], depth);
+
+            let Some(path) = BreadthFirst.nearest(&graph.links, from, |id| categories.is_member(id, &within)) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::IM_A_TEAPOT)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            let path = path
+                .iter()
+                .map(|&id| serde_json::json!({
+                    "title": rev_title_map[id],
+                    "categories": categories.titles_of(id),
+                    "in_category": categories.is_member(id, &within),
+                }))
+                .collect::<Vec<_>>();
+
+            let resp = serde_json::json!({
+                "category": categories.titles[category as usize],
+                "depth": depth,
+                "subcategories": within.iter().filter(|&&w| w).count() - 1,
+                "path": path,
+            });
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(resp.to_string()))
+                .unwrap())
+        }
+
+        (&Method::POST, "/categories") => {
+            // titles one per line, e.g. a path; answers title and its categories
+            // separated by `|`, tab separated. With ?in=C1|C2[&depth=1] only those
+            // categories (or a subcategory of them) are listed, and with &filter=1
+            // titles in none of them are left out.
+            let params = query_params(&req);
+            let depth = params.get("depth").and_then(|d| d.parse().ok()).unwrap_or(1).min(categories::MAX_DEPTH);
+            let filter = params.get("filter").is_some_and(|f| f == "1");
+
+            let Some(categories) = &graph.categories else {
+                return Ok(Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .body(full("this graph has no categories"))
+                    .unwrap());
+            };
+
+            // each requested category with the categories it stands for
+            let mut wanted = vec![];
+            for name in params.get("in").map(|c| c.split('|').collect::<Vec<_>>()).unwrap_or_default() {
+                let Some(category) = categories.find(name) else {
+                    return Ok(Response::builder()
+                        .status(StatusCode::NOT_FOUND)
+                        .body(full(format!("unknown category {}", name)))
+                        .unwrap());
+                };
+                wanted.push((category, categories.within(&[category], depth)));
             }
 
-            println!("depth= visited={} predecessors={:?}", visited.len(), predecessor.len());
+            let body = req.collect().await?.to_bytes();
+            let body = String::from_utf8_lossy(body.as_ref()).to_string();
+
+            let lines = body
+                .split("\n")
+                .filter(|t| !t.is_empty())
+                .filter_map(|title| {
+                    let listed = match (title_map.get(title), wanted.is_empty()) {
+                        (None, _) => vec![],
+                        (Some(&id), true) => categories.titles_of(id),
+                        (Some(&id), false) => wanted
+                            .iter()
+                            .filter(|(_, within)| categories.is_member(id, within))
+                            .map(|&(category, _)| categories.titles[category as usize].clone())
+                            .collect(),
+                    };
+
+                    if filter && listed.is_empty() { return None; }
+                    Some(format!("{}\t{}", title, listed.join("|")))
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
 
             Ok(Response::builder()
-                .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-                .status(StatusCode::IM_A_TEAPOT)
-                .body(empty())
+                .status(StatusCode::OK)
+                .body(full(lines))
+                .unwrap())
+        }
+
+        (&Method::GET, "/reachable") => {
+            // ?from=A&to=B
+            let params = query_params(&req);
+            let from = params.get("from").and_then(|t| title_map.get(t));
+            let to = params.get("to").and_then(|t| title_map.get(t));
+
+            let (Some(&from), Some(&to)) = (from, to) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            let reachable = graph.components.reachable(from, to).await;
+            let components = graph.components.get();
+            let reachable = serde_json::json!({
+                "reachable": reachable,
+                "from_component": components.of(from),
+                "to_component": components.of(to),
+            });
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(reachable.to_string()))
+                .unwrap())
+        }
+
+        (&Method::GET, "/component") => {
+            // ?title=X[&members=100]
+            let params = query_params(&req);
+            let members = params.get("members").and_then(|m| m.parse().ok()).unwrap_or(100).min(MAX_LIMIT);
+
+            let Some(&id) = params.get("title").and_then(|t| title_map.get(t)) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            let components = graph.components.get();
+            let Some(component) = components.of(id) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .body(full("components are being computed"))
+                    .unwrap());
+            };
+            let info = components.info(graph, &[component], members).remove(0);
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(serde_json::to_string(&info).unwrap()))
+                .unwrap())
+        }
+
+        (&Method::GET, "/components") => {
+            // [?limit=20][&members=5]
+            let params = query_params(&req);
+            let limit = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(20).min(MAX_LIMIT);
+            let members = params.get("members").and_then(|m| m.parse().ok()).unwrap_or(5).min(MAX_LIMIT);
+
+            let components = graph.components.get();
+            let largest = components.largest(limit);
+            let components = serde_json::json!({
+                "count": components.count(),
+                "largest": components.info(graph, &largest, members),
+            });
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(components.to_string()))
                 .unwrap())
         }
 
@@ -97,60 +725,141 @@ pub async fn service(req: Request<Incoming>, articles: Arc<Vec<Vec<usize>>>, tit
 
             let (Some(&start), Some(&end)) = (title_map.get(&start), title_map.get(&end)) else {
                 return Ok(Response::builder()
-                    .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
                     .status(StatusCode::NOT_FOUND)
                     .body(empty())
                     .unwrap());
             };
 
-            let mut stack = Vec::<usize>::with_capacity(articles.len());
-            let mut visited = vec![false; articles.len()];
-            let mut visit_count = 0;
-            let mut predecessor = vec![0usize; articles.len()];
+            let body = match DepthFirst.path(&graph.links, start, end) {
+                Some(path) => full(graph.titles(&path).join("\n")),
+                None => empty(),
+            };
 
-            stack.push(start);
-            visited[start] = true;
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .body(body)
+                .unwrap())
+        }
 
-            while !stack.is_empty() {
-                let curr = stack.pop().unwrap();
+        (&Method::POST, "/compare") => {
+            // old graph, new graph, start and end title, one per line
+            let body = req.collect().await?.to_bytes();
+            let body = String::from_utf8_lossy(body.as_ref()).to_string();
+            let lines = body.split("\n").filter(|l| !l.is_empty()).collect::<Vec<_>>();
 
-                if curr == end {
-                    let mut curr = end;
-                    let mut path = vec![rev_title_map[curr].clone()];
-                    while curr != start {
-                        curr = predecessor[curr];
-                        path.push(rev_title_map[curr].clone());
-                    }
+            let [old, new, start, end] = lines[..] else {
+                return Ok(Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(full("expected old graph, new graph, start and end on separate lines"))
+                    .unwrap());
+            };
 
-                    path.reverse();
+            let (Some(old), Some(new)) = (registry.get(old), registry.get(new)) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
 
-                    return Ok(Response::builder()
-                        .status(StatusCode::OK)
-                        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-                        .body(full(path.join("\n")))
-                        .unwrap());
-                }
+            let diff = compare::compare(&old.current(), &new.current(), start, end);
 
-                let adj = &articles[curr];
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(serde_json::to_string(&diff).unwrap()))
+                .unwrap())
+        }
 
-                for &link in adj {
-                    if visited[link] { continue }
+        (&Method::GET, "/neighborhood") => {
+            // ?title=X[&hops=1][&limit=100][&to=Y]
+            let params = query_params(&req);
+            let hops = params.get("hops").and_then(|h| h.parse().ok()).unwrap_or(1).min(MAX_HOPS);
+            let limit = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(100).min(MAX_LIMIT);
 
-                    predecessor[link] = curr;
-                    visited[link] = true;
-                    stack.push(link);
-                    visit_count += 1;
-                }
+            let Some(title) = params.get("title") else {
+                return Ok(Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(full("expected a title parameter"))
+                    .unwrap());
+            };
+
+            let center = title_map.get(title);
+            let to = params.get("to").map(|t| title_map.get(t));
+
+            let (Some(&center), None | Some(Some(_))) = (center, to) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            // the paths to `to` may search much of the graph
+            let to = to.flatten().copied();
+            let subgraph = tokio::task::spawn_blocking({
+                let graph = Arc::clone(graph);
+                move || neighborhood::neighborhood(&graph, center, hops, limit, to)
+            }).await.unwrap();
 
-                if visit_count % 10 == 0 { println!("{}", visit_count) }
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(serde_json::to_string(&subgraph).unwrap()))
+                .unwrap())
+        }
+
+        (&Method::GET, "/similar") => {
+            // ?title=X[&limit=20]
+            if graph.minhash.is_empty() {
+                return Ok(Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .body(full("similar articles are not enabled, see WIKILINKS_SIMILAR"))
+                    .unwrap());
             }
 
-            println!("visited={}", visit_count);
+            let params = query_params(&req);
+            let limit = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(20).min(MAX_LIMIT);
+
+            let Some(&id) = params.get("title").and_then(|t| title_map.get(t)) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            let similar = graph.minhash.similar(graph, id, limit);
 
             Ok(Response::builder()
                 .status(StatusCode::OK)
-                .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
-                .body(empty())
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(serde_json::to_string(&similar).unwrap()))
+                .unwrap())
+        }
+
+        (&Method::GET, "/suggest") => {
+            // ?title=X[&method=adamic_adar|jaccard][&limit=20]
+            let params = query_params(&req);
+            let limit = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(20).min(MAX_LIMIT);
+
+            let Some(method) = suggest::Method::parse(params.get("method").map(String::as_str).unwrap_or("adamic_adar")) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::BAD_REQUEST)
+                    .body(full("expected method=adamic_adar or jaccard"))
+                    .unwrap());
+            };
+
+            let Some(&target) = params.get("title").and_then(|t| title_map.get(t)) else {
+                return Ok(Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(empty())
+                    .unwrap());
+            };
+
+            let candidates = suggest::suggest(graph, target, method, limit);
+
+            Ok(Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(full(serde_json::to_string(&candidates).unwrap()))
                 .unwrap())
         }
 
@@ -158,18 +867,13 @@ pub async fn service(req: Request<Incoming>, articles: Arc<Vec<Vec<usize>>>, tit
             let query = req.collect().await?.to_bytes();
             let query = String::from_utf8_lossy(query.as_ref()).to_string();
 
-            let results = query!("SELECT title FROM articles WHERE lower(title) LIKE $1 ORDER BY length(title) ASC, title ASC LIMIT 10", query.to_lowercase())
-                .fetch_all(&*conn)
+            let results = graph.search(&query, 10)
                 .await
                 .unwrap()
-                .into_iter()
-                .map(|r| r.title)
-                .collect::<Vec<_>>()
                 .join("\n");
 
             let resp = Response::builder()
                 .status(StatusCode::OK)
-                .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
                 .body(full(results))
                 .unwrap();
 
@@ -179,9 +883,16 @@ pub async fn service(req: Request<Incoming>, articles: Arc<Vec<Vec<usize>>>, tit
         _ => {
             Ok(Response::builder()
                 .status(StatusCode::NOT_FOUND)
-                .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
                 .body(empty())
                 .unwrap())
         }
     }
-}
\ No newline at end of file
+}
+
+/// Admin routes are only allowed with the admin token, and never without one.
+fn authorized(req: &Request<Incoming>) -> bool {
+    std::env::var("WIKILINKS_ADMIN_TOKEN")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .is_some_and(|t| req.headers().get(header::AUTHORIZATION).is_some_and(|h| h.as_bytes() == format!("Bearer {}", t).as_bytes()))
+}
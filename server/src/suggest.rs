@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::graph::Graph;
+
+/// Shared neighbours with more links than this are skipped. Nearly everything
+/// is a neighbour of them, so they say little and cost the most.
+const MAX_NEIGHBOUR_DEGREE: usize = 10_000;
+/// Most shared neighbours listed as evidence for a candidate.
+const MAX_EVIDENCE: usize = 10;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Method {
+    /// Shared neighbours weighted by `1 / ln(degree)`, so rarer ones count more.
+    AdamicAdar,
+    /// Shared neighbours over all neighbours of both.
+    Jaccard,
+}
+
+impl Method {
+    pub fn parse(s: &str) -> Option<Method> {
+        match s {
+            "adamic_adar" => Some(Method::AdamicAdar),
+            "jaccard" => Some(Method::Jaccard),
+            _ => None,
+        }
+    }
+}
+
+/// An article that probably should link to the one asked about.
+#[derive(Serialize)]
+pub struct Candidate {
+    pub title: String,
+    pub score: f64,
+    /// Number of neighbours the two share.
+    pub shared: usize,
+    /// Some of the shared neighbours, least linked first.
+    pub evidence: Vec<String>,
+}
+
+/// Articles sharing many neighbours (links in either direction) with `target`
+/// that don't link to it yet, best first.
+pub fn suggest(graph: &Graph, target: usize, method: Method, limit: usize) -> Vec<Candidate> {
+    let target_neighbours = neighbours(graph, target);
+
+    // one buffer for the neighbours of every article looked at
+    let mut buffer = vec![];
+
+    // shared neighbour count and Adamic-Adar score of every candidate
+    let mut found = HashMap::<usize, (usize, f64)>::new();
+    for &shared in &target_neighbours {
+        let degree = graph.links.degree(shared);
+        if degree > MAX_NEIGHBOUR_DEGREE { continue; }
+
+        let weight = 1.0 / (degree.max(2) as f64).ln();
+        neighbours_into(graph, shared, &mut buffer);
+        for &candidate in &buffer {
+            let entry = found.entry(candidate).or_default();
+            entry.0 += 1;
+            entry.1 += weight;
+        }
+    }
+
+    let mut ranked = found
+        .into_iter()
+        .filter(|&(candidate, _)| candidate != target && !graph.links.has_edge(candidate, target))
+        .map(|(candidate, (shared, adamic_adar))| {
+            let score = match method {
+                Method::AdamicAdar => adamic_adar,
+                Method::Jaccard => {
+                    // every candidate needs its own neighbours counted to be ranked
+                    neighbours_into(graph, candidate, &mut buffer);
+                    let union = target_neighbours.len() + buffer.len() - shared;
+                    shared as f64 / union as f64
+                }
+            };
+            (candidate, shared, score)
+        })
+        .collect::<Vec<_>>();
+
+    ranked.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| graph.rev_title_map[a.0].cmp(&graph.rev_title_map[b.0])));
+    ranked.truncate(limit);
+
+    ranked
+        .into_iter()
+        .map(|(candidate, shared, score)| {
+            let mut evidence = intersection(&target_neighbours, &neighbours(graph, candidate))
+                .into_iter()
+                .filter(|&n| graph.links.degree(n) <= MAX_NEIGHBOUR_DEGREE)
+                .collect::<Vec<_>>();
+            evidence.sort_by_key(|&n| (graph.links.degree(n), n));
+            evidence.truncate(MAX_EVIDENCE);
+
+            Candidate { title: graph.rev_title_map[candidate].clone(), score, shared, evidence: graph.titles(&evidence) }
+        })
+        .collect()
+}
+
+/// Articles linking to or linked from `id`, sorted and each listed once.
+fn neighbours(graph: &Graph, id: usize) -> Vec<usize> {
+    let mut all = vec![];
+    neighbours_into(graph, id, &mut all);
+    all
+}
+
+/// [`neighbours`] into a buffer, replacing what it held.
+fn neighbours_into(graph: &Graph, id: usize, out: &mut Vec<usize>) {
+    out.clear();
+    out.extend(graph.links.articles[id].iter().chain(&graph.links.backlinks[id]));
+    out.sort_unstable();
+    out.dedup();
+}
+
+fn intersection(a: &[usize], b: &[usize]) -> Vec<usize> {
+    a.iter().copied().filter(|x| b.binary_search(x).is_ok()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0 links to 1 and 2 and is linked from 3. 4 and 6 share 1 and 2 with it,
+    /// but 6 has many other neighbours; 5 shares only 1, and 1 shares only 3.
+    fn graph() -> Graph {
+        Graph::from_links(vec![
+            vec![1, 2],
+            vec![],
+            vec![],
+            vec![0, 1],
+            vec![1, 2],
+            vec![1],
+            vec![1, 2, 7, 8, 9, 10],
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+        ])
+    }
+
+    fn titles(candidates: &[Candidate]) -> Vec<&str> {
+        candidates.iter().map(|c| c.title.as_str()).collect()
+    }
+
+    #[test]
+    fn adamic_adar_favours_rare_shared_neighbours() {
+        let found = suggest(&graph(), 0, Method::AdamicAdar, 10);
+        assert_eq!(titles(&found), ["4", "6", "1", "5"]);
+        assert_eq!(found[0].shared, 2);
+        assert!((found[0].score - (1.0 / 5f64.ln() + 1.0 / 3f64.ln())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn jaccard_penalises_other_neighbours() {
+        let found = suggest(&graph(), 0, Method::Jaccard, 10);
+        assert_eq!(titles(&found), ["4", "5", "6", "1"]);
+        assert!((found[0].score - 2.0 / 3.0).abs() < 1e-9);
+        assert!((found[2].score - 2.0 / 7.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn the_target_and_its_linkers_are_left_out() {
+        let found = suggest(&graph(), 0, Method::AdamicAdar, 10);
+        assert!(!titles(&found).contains(&"0"));
+        assert!(!titles(&found).contains(&"3"));
+    }
+
+    #[test]
+    fn limit_keeps_the_best() {
+        assert_eq!(titles(&suggest(&graph(), 0, Method::Jaccard, 2)), ["4", "5"]);
+    }
+
+    #[test]
+    fn evidence_lists_the_least_linked_first() {
+        let found = suggest(&graph(), 0, Method::AdamicAdar, 1);
+        assert_eq!(found[0].evidence, ["2", "1"]);
+    }
+
+    #[test]
+    fn hubs_are_not_shared_neighbours() {
+        // 0 links to the hub 1 and to 2; 3 shares only the hub, 4 shares 2
+        let hub_linkers = 5..MAX_NEIGHBOUR_DEGREE + 6;
+        let mut links = vec![vec![1, 2], vec![], vec![], vec![1], vec![2]];
+        links.extend(hub_linkers.map(|_| vec![1]));
+
+        let found = suggest(&Graph::from_links(links), 0, Method::AdamicAdar, 10);
+        assert_eq!(titles(&found), ["4"]);
+        assert_eq!(found[0].evidence, ["2"]);
+    }
+}
@@ -0,0 +1,125 @@
+use serde::Deserialize;
+use crate::categories;
+use crate::graph::Graph;
+
+/// The body of `POST /nearest`: a start title and one way of naming the
+/// targets, e.g. `{"from": "A", "titles": ["B", "C"]}`,
+/// `{"from": "A", "prefix": "List of "}` or
+/// `{"from": "A", "category": "Physics", "depth": 2}`.
+#[derive(Deserialize)]
+pub struct NearestQuery {
+    pub from: String,
+    #[serde(flatten)]
+    pub targets: Targets,
+    /// Subcategory levels followed for a category, default 1.
+    pub depth: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Targets {
+    /// Titles that are not articles of the graph are ignored.
+    Titles(Vec<String>),
+    /// Every article whose title starts with this. Checks every title.
+    Prefix(String),
+    /// Every article in the category or its subcategories.
+    Category(String),
+}
+
+#[derive(Debug)]
+pub enum TargetError {
+    /// The graph has no categories (it is a snapshot).
+    NoCategories,
+    UnknownCategory,
+}
+
+impl NearestQuery {
+    /// For every article id, whether it is a target. A prefix or category
+    /// looks at every article, and the answer takes a byte per article; the
+    /// search then tests each article it reaches in constant time.
+    pub fn resolve(&self, graph: &Graph) -> Result<Vec<bool>, TargetError> {
+        let mut targets = vec![false; graph.links.articles.len()];
+
+        match &self.targets {
+            Targets::Titles(titles) => {
+                for &id in titles.iter().filter_map(|t| graph.title_map.get(t)) {
+                    targets[id] = true;
+                }
+            }
+            Targets::Prefix(prefix) => {
+                for (id, title) in graph.rev_title_map.iter().enumerate() {
+                    targets[id] = !title.is_empty() && title.starts_with(prefix.as_str());
+                }
+            }
+            Targets::Category(name) => {
+                let categories = graph.categories.as_ref().ok_or(TargetError::NoCategories)?;
+                let category = categories.find(name).ok_or(TargetError::UnknownCategory)?;
+
+                let depth = self.depth.unwrap_or(1).min(categories::MAX_DEPTH);
+                let within = categories.within(&[category], depth);
+                for (id, target) in targets.iter_mut().enumerate() {
+                    *target = categories.is_member(id, &within);
+                }
+            }
+        }
+
+        Ok(targets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use super::*;
+    use crate::categories::Categories;
+
+    fn query(json: &str) -> NearestQuery {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn chosen(targets: &[bool]) -> Vec<usize> {
+        targets.iter().enumerate().filter(|(_, &t)| t).map(|(id, _)| id).collect()
+    }
+
+    fn graph() -> Graph {
+        let mut graph = Graph::from_links(vec![vec![]; 4]);
+        graph.rev_title_map = ["List of cats", "List of dogs", "Cat", ""].map(String::from).to_vec();
+        graph.title_map = graph.rev_title_map.iter().cloned().zip(0..).filter(|(t, _)| !t.is_empty()).collect();
+        graph
+    }
+
+    #[test]
+    fn titles_that_are_not_articles_are_ignored() {
+        let targets = query(r#"{"from": "Cat", "titles": ["Cat", "Dog", "List of dogs"]}"#).resolve(&graph());
+        assert_eq!(chosen(&targets.unwrap()), [1, 2]);
+    }
+
+    #[test]
+    fn a_prefix_matches_titles_but_not_deleted_articles() {
+        let targets = query(r#"{"from": "Cat", "prefix": "List of "}"#).resolve(&graph());
+        assert_eq!(chosen(&targets.unwrap()), [0, 1]);
+
+        let targets = query(r#"{"from": "Cat", "prefix": ""}"#).resolve(&graph());
+        assert_eq!(chosen(&targets.unwrap()), [0, 1, 2]);
+    }
+
+    #[test]
+    fn a_category_includes_subcategories_down_to_the_depth() {
+        let mut graph = graph();
+        assert!(matches!(query(r#"{"from": "Cat", "category": "Animals"}"#).resolve(&graph), Err(TargetError::NoCategories)));
+
+        // Animals > Mammals > Felines
+        graph.categories = Some(Arc::new(Categories::from_titles(
+            &[&["Category:Lists"], &["Category:Lists", "Category:Mammals"], &["Category:Felines"]],
+            &[("Category:Mammals", &["Category:Animals"]), ("Category:Felines", &["Category:Mammals"])],
+        )));
+
+        let targets = query(r#"{"from": "Cat", "category": "Animals"}"#).resolve(&graph);
+        assert_eq!(chosen(&targets.unwrap()), [1]);
+
+        let targets = query(r#"{"from": "Cat", "category": "Category:Animals", "depth": 2}"#).resolve(&graph);
+        assert_eq!(chosen(&targets.unwrap()), [1, 2]);
+
+        assert!(matches!(query(r#"{"from": "Cat", "category": "Plants"}"#).resolve(&graph), Err(TargetError::UnknownCategory)));
+    }
+}
@@ -0,0 +1,136 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use arc_swap::ArcSwap;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::server::{ClientHello, ResolvesServerCert};
+use tokio_rustls::rustls::sign::CertifiedKey;
+use tokio_rustls::rustls::ServerConfig;
+use tokio_rustls::TlsAcceptor;
+
+/// A certificate chain and private key read from PEM files, served to every
+/// client and replaced when the files change.
+pub struct Certificates {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    current: ArcSwap<CertifiedKey>,
+    /// Modification times of the files last read, loaded or not.
+    modified: Mutex<(SystemTime, SystemTime)>,
+}
+
+impl Certificates {
+    pub fn load(cert_path: &Path, key_path: &Path) -> Result<Certificates, Box<dyn Error + Send + Sync>> {
+        let modified = modified(cert_path, key_path)?;
+        let key = read(cert_path, key_path)?;
+
+        Ok(Certificates {
+            cert_path: cert_path.to_path_buf(),
+            key_path: key_path.to_path_buf(),
+            current: ArcSwap::from_pointee(key),
+            modified: Mutex::new(modified),
+        })
+    }
+
+    /// Read the files again if either changed since the last time. Returns
+    /// whether the certificate was replaced; on an error the old one stays.
+    pub fn reload_if_changed(&self) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let modified = modified(&self.cert_path, &self.key_path)?;
+
+        let mut last = self.modified.lock().unwrap();
+        if *last == modified { return Ok(false); }
+        // remember them even if reading fails, a half written pair gets
+        // another try when the other file changes
+        *last = modified;
+
+        self.current.store(Arc::new(read(&self.cert_path, &self.key_path)?));
+        Ok(true)
+    }
+
+    /// An acceptor offering HTTP/2 and HTTP/1.1 over ALPN.
+    pub fn acceptor(self: &Arc<Self>) -> Result<TlsAcceptor, Box<dyn Error + Send + Sync>> {
+        let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::clone(self) as Arc<dyn ResolvesServerCert>);
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+impl ResolvesServerCert for Certificates {
+    fn resolve(&self, _: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.load_full())
+    }
+}
+
+impl fmt::Debug for Certificates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Certificates")
+            .field("cert_path", &self.cert_path)
+            .field("key_path", &self.key_path)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The certificates in `WIKILINKS_TLS_CERT` and `WIKILINKS_TLS_KEY`, PEM files
+/// with the chain (leaf first) and the private key. `None` serves plain HTTP.
+pub fn configured() -> Result<Option<Arc<Certificates>>, Box<dyn Error + Send + Sync>> {
+    let cert = std::env::var_os("WIKILINKS_TLS_CERT");
+    let key = std::env::var_os("WIKILINKS_TLS_KEY");
+
+    match (cert, key) {
+        (None, None) => Ok(None),
+        (Some(cert), Some(key)) => Ok(Some(Arc::new(Certificates::load(cert.as_ref(), key.as_ref())?))),
+        _ => Err("WIKILINKS_TLS_CERT and WIKILINKS_TLS_KEY must be set together".into()),
+    }
+}
+
+/// Check the files every `interval` and reload them when they change, e.g.
+/// after a renewal. Runs forever.
+pub async fn watch(certificates: Arc<Certificates>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+
+        match certificates.reload_if_changed() {
+            Ok(false) => {}
+            Ok(true) => println!("Reloaded the certificate from {}", certificates.cert_path.display()),
+            Err(e) => eprintln!("Failed to reload the certificate, keeping the old one: {}", e),
+        }
+    }
+}
+
+fn modified(cert_path: &Path, key_path: &Path) -> Result<(SystemTime, SystemTime), Box<dyn Error + Send + Sync>> {
+    let modified = |path: &Path| {
+        std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    };
+
+    Ok((modified(cert_path)?, modified(key_path)?))
+}
+
+fn read(cert_path: &Path, key_path: &Path) -> Result<CertifiedKey, Box<dyn Error + Send + Sync>> {
+    let open = |path: &Path| File::open(path).map(BufReader::new).map_err(|e| format!("{}: {}", path.display(), e));
+
+    let chain = rustls_pemfile::certs(&mut open(cert_path)?)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("{}: {}", cert_path.display(), e))?;
+    if chain.is_empty() {
+        return Err(format!("{}: no certificates", cert_path.display()).into());
+    }
+
+    let key = rustls_pemfile::private_key(&mut open(key_path)?)
+        .map_err(|e| format!("{}: {}", key_path.display(), e))?
+        .ok_or_else(|| format!("{}: no private key", key_path.display()))?;
+
+    let key = CertifiedKey::new(chain, ring::sign::any_supported_type(&key)?);
+    key.keys_match()
+        .map_err(|e| format!("{} doesn't match {}: {}", key_path.display(), cert_path.display(), e))?;
+
+    Ok(key)
+}
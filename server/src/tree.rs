@@ -0,0 +1,148 @@
+use std::io::{self, Write};
+use bytes::Bytes;
+use tokio::sync::mpsc;
+use wikilinks_core::PathTree;
+use crate::graph::Graph;
+
+/// Start of the binary tree format, followed by the version.
+const MAGIC: &[u8; 4] = b"WLTR";
+const VERSION: u32 = 1;
+/// Bytes collected before a chunk is sent.
+const CHUNK: usize = 1 << 16;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    /// One JSON object per article: `id`, `title`, `distance` and the id of
+    /// its `parent`, `null` for the start.
+    Ndjson,
+    /// `WLTR`, the version and the start id, then three numbers per
+    /// article: id, parent id and distance. The start is its own parent.
+    /// Every number is a little-endian u32.
+    Binary,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "ndjson" => Some(Format::Ndjson),
+            "binary" => Some(Format::Binary),
+            _ => None,
+        }
+    }
+}
+
+/// Search the whole graph from `start` and send the tree to `tx` in `format`,
+/// nearest articles first, leaving out those more than `max_distance` links
+/// away. Stops early when the receiver is dropped.
+pub fn stream(graph: &Graph, start: usize, format: Format, max_distance: u32, tx: mpsc::Sender<Bytes>) {
+    let mut chunks = Chunks { buf: Vec::with_capacity(CHUNK), tx };
+    if write(graph, start, format, max_distance, &mut chunks).is_ok() {
+        let _ = chunks.tx.blocking_send(Bytes::from(chunks.buf));
+    }
+}
+
+/// Search the whole graph from `start` and write the tree to `out` like
+/// [`stream`] does.
+pub fn write(graph: &Graph, start: usize, format: Format, max_distance: u32, out: &mut impl Write) -> io::Result<()> {
+    let tree = PathTree::new(&graph.links, start);
+
+    if format == Format::Binary {
+        out.write_all(MAGIC)?;
+        out.write_all(&VERSION.to_le_bytes())?;
+        out.write_all(&(start as u32).to_le_bytes())?;
+    }
+
+    for &id in tree.order.iter().take_while(|&&id| tree.distance[id] <= max_distance) {
+        let (parent, distance) = (tree.parent[id], tree.distance[id]);
+
+        match format {
+            Format::Ndjson => {
+                let line = serde_json::json!({
+                    "id": id,
+                    "title": graph.rev_title_map[id],
+                    "distance": distance,
+                    "parent": (id != start).then_some(parent),
+                });
+                serde_json::to_writer(&mut *out, &line)?;
+                out.write_all(b"\n")?;
+            }
+            Format::Binary => {
+                for n in [id as u32, parent, distance] {
+                    out.write_all(&n.to_le_bytes())?;
+                }
+            }
+        }
+    }
+
+    out.flush()
+}
+
+/// Collects writes and sends them on in chunks of at least [`CHUNK`] bytes,
+/// failing once the receiver is gone.
+struct Chunks {
+    buf: Vec<u8>,
+    tx: mpsc::Sender<Bytes>,
+}
+
+impl Write for Chunks {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(bytes);
+
+        if self.buf.len() >= CHUNK {
+            let chunk = Bytes::from(std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK)));
+            self.tx.blocking_send(chunk).map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use super::*;
+
+    /// 0 -> 1 -> 3 and 0 -> 2, with 4 out of reach.
+    fn graph() -> Graph {
+        Graph::from_links(vec![vec![1, 2], vec![3], vec![], vec![], vec![0]])
+    }
+
+    fn ndjson(max_distance: u32) -> Vec<Value> {
+        let mut out = vec![];
+        write(&graph(), 0, Format::Ndjson, max_distance, &mut out).unwrap();
+        String::from_utf8(out).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn ndjson_lists_reachable_articles_nearest_first() {
+        assert_eq!(ndjson(u32::MAX), [
+            json!({"id": 0, "title": "0", "distance": 0, "parent": null}),
+            json!({"id": 1, "title": "1", "distance": 1, "parent": 0}),
+            json!({"id": 2, "title": "2", "distance": 1, "parent": 0}),
+            json!({"id": 3, "title": "3", "distance": 2, "parent": 1}),
+        ]);
+    }
+
+    #[test]
+    fn articles_past_max_distance_are_left_out() {
+        let ids = ndjson(1).iter().map(|line| line["id"].as_u64().unwrap()).collect::<Vec<_>>();
+        assert_eq!(ids, [0, 1, 2]);
+        assert_eq!(ndjson(0).len(), 1);
+    }
+
+    #[test]
+    fn binary_has_a_header_then_three_numbers_per_article() {
+        let mut out = vec![];
+        write(&graph(), 0, Format::Binary, u32::MAX, &mut out).unwrap();
+
+        assert_eq!(&out[..4], MAGIC);
+        let numbers = out[4..].chunks(4).map(|n| u32::from_le_bytes(n.try_into().unwrap())).collect::<Vec<_>>();
+        assert_eq!(numbers[..2], [VERSION, 0]);
+
+        let records = numbers[2..].chunks(3).collect::<Vec<_>>();
+        assert_eq!(records, [[0, 0, 0], [1, 0, 1], [2, 0, 1], [3, 1, 2]]);
+    }
+}
@@ -0,0 +1,183 @@
+//! HTTPS against a small snapshot graph. The certificates in `tests/certs` are
+//! self-signed for localhost, made with
+//! `openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes -days 36500
+//! -subj /CN=localhost -addext subjectAltName=DNS:localhost -addext basicConstraints=critical,CA:FALSE`.
+
+use std::fs::File;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Empty};
+use hyper::{Request, StatusCode};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName};
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+use wikilinks::connections::{self, Limits};
+use wikilinks::graph::{Graph, Registry, Source};
+use wikilinks::tls::Certificates;
+
+const CERTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/certs");
+
+/// A directory of its own for each test, removed when dropped.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new(name: &str) -> Scratch {
+        let dir = std::env::temp_dir().join(format!("wikilinks-tls-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Scratch(dir)
+    }
+
+    /// Copy a certificate pair from `tests/certs` to `server.crt` and
+    /// `server.key`, dated `age` ago so that every copy looks changed.
+    fn install(&self, pair: &str, age: Duration) -> (PathBuf, PathBuf) {
+        let (cert, key) = (self.0.join("server.crt"), self.0.join("server.key"));
+
+        for (from, to) in [(format!("{}.crt", pair), &cert), (format!("{}.key", pair), &key)] {
+            std::fs::copy(Path::new(CERTS).join(from), to).unwrap();
+            File::options().write(true).open(to).unwrap().set_modified(SystemTime::now() - age).unwrap();
+        }
+
+        (cert, key)
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Serve a two article graph over TLS on a free port until the sender is
+/// dropped or used.
+async fn start(scratch: &Scratch, certificates: &Arc<Certificates>) -> (SocketAddr, oneshot::Sender<()>) {
+    let snapshot = scratch.0.join("graph.tsv");
+    std::fs::write(&snapshot, "1\tAlpha\t2\n2\tBeta\t\n").unwrap();
+
+    let graph = Graph::load("test", Source::Snapshot(snapshot)).await.unwrap();
+    let registry = Arc::new(Registry::new(vec![graph]));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let limits = Limits { max_connections: 8, idle_timeout: Duration::from_secs(5), drain_timeout: Duration::from_secs(5) };
+
+    let (stop, stopped) = oneshot::channel::<()>();
+    let acceptor = certificates.acceptor().unwrap();
+    tokio::spawn(connections::serve(listener, registry, limits, Some(acceptor), async {
+        let _ = stopped.await;
+    }));
+
+    (addr, stop)
+}
+
+/// Connect trusting only the certificate in `tests/certs/{pair}.crt`.
+async fn connect(addr: SocketAddr, pair: &str, alpn: &[&[u8]]) -> std::io::Result<TlsStream<TcpStream>> {
+    let pem = std::fs::read(Path::new(CERTS).join(format!("{}.crt", pair))).unwrap();
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut pem.as_slice()) {
+        roots.add(cert.unwrap()).unwrap();
+    }
+
+    let mut config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    config.alpn_protocols = alpn.iter().map(|p| p.to_vec()).collect();
+
+    let stream = TcpStream::connect(addr).await?;
+    TlsConnector::from(Arc::new(config))
+        .connect(ServerName::try_from("localhost").unwrap(), stream)
+        .await
+}
+
+fn served_certificate(stream: &TlsStream<TcpStream>) -> CertificateDer<'static> {
+    stream.get_ref().1.peer_certificates().unwrap()[0].clone().into_owned()
+}
+
+fn certificate(pair: &str) -> CertificateDer<'static> {
+    let pem = std::fs::read(Path::new(CERTS).join(format!("{}.crt", pair))).unwrap();
+    let cert = rustls_pemfile::certs(&mut pem.as_slice()).next().unwrap().unwrap();
+    cert
+}
+
+#[tokio::test]
+async fn serves_http1_over_tls() {
+    let scratch = Scratch::new("http1");
+    let (cert, key) = scratch.install("first", Duration::ZERO);
+    let certificates = Arc::new(Certificates::load(&cert, &key).unwrap());
+    let (addr, _stop) = start(&scratch, &certificates).await;
+
+    let mut stream = connect(addr, "first", &[b"http/1.1"]).await.unwrap();
+    assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"http/1.1"[..]));
+
+    stream.write_all(b"GET /article/Alpha HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+    assert!(response.contains(r#""links":["Beta"]"#), "{}", response);
+}
+
+#[tokio::test]
+async fn negotiates_http2() {
+    let scratch = Scratch::new("http2");
+    let (cert, key) = scratch.install("first", Duration::ZERO);
+    let certificates = Arc::new(Certificates::load(&cert, &key).unwrap());
+    let (addr, _stop) = start(&scratch, &certificates).await;
+
+    let stream = connect(addr, "first", &[b"h2", b"http/1.1"]).await.unwrap();
+    assert_eq!(stream.get_ref().1.alpn_protocol(), Some(&b"h2"[..]));
+
+    let (mut sender, conn) = hyper::client::conn::http2::handshake(TokioExecutor::new(), TokioIo::new(stream)).await.unwrap();
+    tokio::spawn(conn);
+
+    let request = Request::get("https://localhost/article/Beta").body(Empty::<Bytes>::new()).unwrap();
+    let response = sender.send_request(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(body.starts_with(br#"{"id":2,"title":"Beta""#), "{:?}", body);
+}
+
+#[tokio::test]
+async fn reloads_changed_certificates() {
+    let scratch = Scratch::new("reload");
+    let (cert, key) = scratch.install("first", Duration::from_secs(60));
+    let certificates = Arc::new(Certificates::load(&cert, &key).unwrap());
+    let (addr, _stop) = start(&scratch, &certificates).await;
+
+    assert!(!certificates.reload_if_changed().unwrap());
+    let stream = connect(addr, "first", &[]).await.unwrap();
+    assert_eq!(served_certificate(&stream), certificate("first"));
+
+    scratch.install("second", Duration::ZERO);
+    assert!(certificates.reload_if_changed().unwrap());
+
+    let stream = connect(addr, "second", &[]).await.unwrap();
+    assert_eq!(served_certificate(&stream), certificate("second"));
+    assert!(connect(addr, "first", &[]).await.is_err());
+}
+
+#[tokio::test]
+async fn keeps_the_old_certificate_when_the_key_does_not_match() {
+    let scratch = Scratch::new("mismatch");
+    let (cert, key) = scratch.install("first", Duration::from_secs(60));
+    let certificates = Arc::new(Certificates::load(&cert, &key).unwrap());
+    let (addr, _stop) = start(&scratch, &certificates).await;
+
+    std::fs::copy(Path::new(CERTS).join("second.key"), &key).unwrap();
+    assert!(certificates.reload_if_changed().is_err());
+    assert!(Certificates::load(&cert, &key).is_err());
+
+    let stream = connect(addr, "first", &[]).await.unwrap();
+    assert_eq!(served_certificate(&stream), certificate("first"));
+}